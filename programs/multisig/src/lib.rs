@@ -1,33 +1,207 @@
+// anchor 0.31's #[program] emits IDL handlers at crate scope that call the
+// deprecated AccountInfo::realloc, so no narrower allow reaches them. Drop this
+// on the next anchor upgrade; our own deprecated uses are marked individually.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
+// Durable nonces still need the RecentBlockhashes sysvar, and system_instruction
+// is what this anchor-lang version re-exports for building system calls
+#[allow(deprecated)]
 use anchor_lang::solana_program::{
-    system_instruction,
-    program::invoke_signed,
+    system_instruction::{self, SystemInstruction},
     sysvar::recent_blockhashes::RecentBlockhashes,
 };
+use anchor_lang::solana_program::{
+    program::{invoke, invoke_signed},
+    nonce::state::{State as NonceState, Versions as NonceVersions},
+    ed25519_program,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
 
 declare_id!("9ci6bSKQcGTEFGiDTRHacAf84jKuzwE3X5vHBWTDu5nb");
 
 // Move constants outside the module to global scope
-const MAX_OWNERS: usize = 10;
+// Hard cap to keep owner iteration well within compute limits
+const MAX_OWNERS: usize = 64;
+// Extra owner slots allocated at initialize so the first few add_owner calls don't need to realloc
+const OWNER_HEADROOM: usize = 2;
 const MAX_STORED_NONCES: usize = 100;
 const MAX_INSTRUCTION_ACCOUNTS: usize = 10;
+// Per-instruction cap when lookup table references are used. The proposal as a
+// whole still has to fit MAX_INSTRUCTIONS * MAX_INSTRUCTION_ACCOUNTS accounts.
+const MAX_LOOKUP_INSTRUCTION_ACCOUNTS: usize = 32;
+// Hard cap for a multisig's max_data_size
 const MAX_INSTRUCTION_DATA_SIZE: usize = 1024;
+const MAX_INSTRUCTIONS: usize = 5;
+const SECONDS_PER_DAY: i64 = 86_400;
+// threshold_bps is expressed out of this
+const BPS_DENOMINATOR: u16 = 10_000;
+// Bounds the number of live proposal PDAs per multisig
+const MAX_OPEN_TRANSACTIONS: u16 = 32;
+// Current Multisig layout, bumped whenever fields are appended
+const MULTISIG_VERSION: u8 = 26;
+// Upper bound for max_tx_per_window, which sizes recent_creations
+const MAX_RATE_LIMIT: usize = 32;
+// Keeps approve_many within compute limits
+const MAX_BATCH_SIZE: usize = 10;
+// Approver memos kept per transaction, oldest dropped first. Kept small so a
+// Transaction at the largest max_data_size stays within the 10 KiB CPI limit.
+const MAX_APPROVAL_MEMOS: usize = 3;
+// Max bytes of a proposal's free-form description
+const MAX_DESCRIPTION_LEN: usize = 128;
+// Sizes the tracked_mints vec
+const MAX_TRACKED_MINTS: usize = 16;
+// Sizes the allowed_programs vec
+const MAX_ALLOWED_PROGRAMS: usize = 16;
+// Sizes the allowed_recipients vec
+const MAX_ALLOWED_RECIPIENTS: usize = 32;
+// Max distinct roles a multisig can require among the approvers
+const MAX_REQUIRED_ROLES: usize = 4;
+// Delegations and veto owners are fixed-size lists rather than scaling with the
+// owner capacity, keeping a multisig at MAX_OWNERS within the CPI allocation limit
+const MAX_DELEGATES: usize = 16;
+const MAX_VETO_OWNERS: usize = 8;
+
+// First seed of every transaction PDA, exported to the IDL for clients
+#[constant]
+pub const TRANSACTION_SEED: &[u8] = b"transaction";
+
+const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+const ADDRESS_LOOKUP_TABLE_PROGRAM_ID: Pubkey = pubkey!("AddressLookupTab1e1111111111111111111111111");
+// Addresses in a lookup table account start after its metadata header
+const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+// Guardians are a small recovery committee, not a second owner set
+const MAX_GUARDIANS: usize = 10;
+// Window in which owners can cancel a recovery the guardians approved
+const RECOVERY_DELAY: i64 = 3 * SECONDS_PER_DAY;
+// How long a nominated owner has to call accept_ownership
+const NOMINATION_WINDOW: i64 = 7 * SECONDS_PER_DAY;
+// How long guardians have to confirm a held large outflow
+const LARGE_OUTFLOW_WINDOW: i64 = SECONDS_PER_DAY;
+// Executor rewards only need to cover fees, 0.01 SOL
+const MAX_EXECUTOR_REWARD: u64 = 10_000_000;
+
+const RECOVERY_SPACE: usize = 8 +    // discriminator
+    32 +                             // multisig
+    32 +                             // proposer
+    4 + (32 * MAX_OWNERS) +          // new_owners vec
+    1 +                              // new_threshold
+    4 + (32 * MAX_GUARDIANS) +       // approvals vec
+    8;                               // eta
+
+// Prefix of the off-chain approval message, see approval_message
+const APPROVAL_DOMAIN: &[u8] = b"multisig-approval";
 
 #[program]
 pub mod multisig {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>, multisig_id: u64, owners: Vec<Pubkey>, threshold: u8) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        multisig_id: u64,
+        owners: Vec<Pubkey>,
+        threshold: u8,
+        min_delay: i64,
+        weights: Vec<u16>,
+        name: [u8; 32],
+        default_tx_ttl: i64,
+        allow_self_calls: bool,
+        legacy_nonces: bool,
+        max_data_size: u16,
+        min_owners: u8,
+        id_seed: Option<String>,
+        weight_threshold: u64,
+    ) -> Result<u64> {
         let multisig = &mut ctx.accounts.multisig;
         let creator = &ctx.accounts.creator;
 
+        // With a seed the id is derived from it and the supplied multisig_id is ignored
+        let multisig_id = resolve_multisig_id(multisig_id, &creator.key(), id_seed.as_deref());
+
+        require!(owners.len() <= MAX_OWNERS, ErrorCode::TooManyOwners);
+
+        require!(default_tx_ttl >= 0, ErrorCode::InvalidTtl);
+        require!(
+            max_data_size > 0 && max_data_size as usize <= MAX_INSTRUCTION_DATA_SIZE,
+            ErrorCode::InvalidDataSize
+        );
+
+        // Empty weights means one owner, one vote
+        require!(
+            weights.is_empty() || weights.len() == owners.len(),
+            ErrorCode::WeightsMismatch
+        );
+
         multisig.owners = owners;
         multisig.threshold = threshold;
+        multisig.threshold_mode = ThresholdMode::Absolute;
+        multisig.threshold_bps = 0;
         multisig.creator = creator.key();
         multisig.multisig_id = multisig_id;
         multisig.used_nonces = Vec::new();
+        multisig.seq = 0;
+        multisig.legacy_nonces = legacy_nonces;
+        multisig.min_delay = min_delay;
+        multisig.min_vote_spacing = 0;
+        multisig.weights = weights;
+        multisig.name = name;
+        multisig.default_tx_ttl = default_tx_ttl;
+        multisig.allow_self_calls = allow_self_calls;
+        multisig.paused = false;
+        multisig.executing = false;
+        multisig.guardians = Vec::new();
+        multisig.guardian_threshold = 0;
+        multisig.open_tx_count = 0;
+        multisig.max_tx_per_window = 0;
+        multisig.window_secs = 0;
+        multisig.recent_creations = Vec::new();
+        multisig.version = MULTISIG_VERSION;
+        multisig.allowed_programs = Vec::new();
+        multisig.allowed_recipients = Vec::new();
+        multisig.event_seq = 0;
+        multisig.open_nonces = Vec::new();
+        multisig.max_data_size = max_data_size;
+        multisig.min_governance_delay = 0;
+        multisig.pending_governance_change = None;
+        multisig.min_owners = min_owners;
+        multisig.tracked_mints = Vec::new();
+        multisig.reject_duplicate_accounts = false;
+        multisig.bump = ctx.bumps.multisig;
+        multisig.persist_timelock_on_revoke = false;
+        multisig.delegates = Vec::new();
+        multisig.retain_executed_data = false;
+        multisig.roles = Vec::new();
+        multisig.required_roles = Vec::new();
+        multisig.pause_votes = Vec::new();
+        multisig.readonly_threshold = 0;
+        multisig.max_tx_value = 0;
+        multisig.veto_owners = Vec::new();
+        multisig.owner_set_seq = 0;
+        multisig.approval_counts = Vec::new();
+        multisig.executor_must_be_owner = false;
+        multisig.expiry_grace = 0;
+        multisig.pending_nomination = None;
+        multisig.large_outflow_bps = 0;
+        multisig.pending_large_tx = None;
+        multisig.released_large_tx = None;
+        multisig.pinned_nonces = Vec::new();
+        multisig.executing_nonce = 0;
+        multisig.weight_threshold = weight_threshold;
+        multisig.daily_limit = 0;
+        multisig.spent_today = 0;
+        multisig.last_reset_ts = 0;
 
-        if threshold > multisig.owners.len() as u8 {
+        let now = Clock::get()?.unix_timestamp;
+        multisig.created_at = now;
+        multisig.last_activity = now;
+
+        // A weight_threshold stands in for threshold, which is then ignored
+        let required_weight = multisig.required_weight();
+        if required_weight == 0 || required_weight > multisig.total_weight() {
+            msg!("threshold {} must be between 1 and total weight {}", required_weight, multisig.total_weight());
             return Err(ErrorCode::InvalidThreshold.into());
         }
         
@@ -35,6 +209,9 @@ pub mod multisig {
             return Err(ErrorCode::NoOwners.into());
         }
 
+        require!(min_owners > 0, ErrorCode::InvalidMinOwners);
+        require!(multisig.owners.len() >= min_owners as usize, ErrorCode::TooFewOwners);
+
         // Preventing duplicate owners
         let mut unique = std::collections::HashSet::new();
         for owner in &multisig.owners {
@@ -43,61 +220,69 @@ pub mod multisig {
             }
         }
 
-        Ok(())
+        emit!(MultisigInitialized {
+            multisig: multisig.key(),
+            creator: multisig.creator,
+            multisig_id,
+            owners: multisig.owners.clone(),
+            threshold,
+            name,
+            created_at: multisig.created_at,
+            last_activity: multisig.last_activity,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        debug_assert_fits(multisig);
+
+        // Returned so callers using a seed learn the id (and thus the PDA) that was used
+        Ok(multisig_id)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_transaction(
       ctx: Context<CreateTransaction>,
       _multisig_id: u64,
       nonce: u64,
-      program_id: Pubkey,
-      accounts: Vec<TransactionAccount>,
-      data: Vec<u8>
-    ) -> Result<()> {
+      instructions: Vec<TransactionInstruction>,
+      expires_at: i64,
+      executor_reward: u64,
+      depends_on: Option<u64>,
+      priority_hint: Option<PriorityHint>,
+      description: Option<String>,
+      valid_from_slot: u64,
+      valid_until_slot: u64,
+      required_approvals: u8,
+    ) -> Result<CreatedTransaction> {
         
         let proposer = &ctx.accounts.proposer;
 
         // Read-only checks first (before mutable borrow)
-        require!(
-            ctx.accounts.multisig.owners.contains(&proposer.key()),
-            ErrorCode::NotAnOwner
-        );
+        validate_proposal(&ctx.accounts.multisig, &proposer.key(), nonce, &instructions, expires_at)?;
+        validate_pda_signers(&ctx.accounts.multisig.key(), &instructions, ctx.program_id)?;
+        validate_slot_window(valid_from_slot, valid_until_slot)?;
+        validate_required_approvals(&ctx.accounts.multisig, required_approvals)?;
+        require!(executor_reward <= MAX_EXECUTOR_REWARD, ErrorCode::ExecutorRewardTooHigh);
+        let lookup_keys_hash = hash_lookup_keys(&instructions, ctx.remaining_accounts)?;
 
-        require!(
-            !ctx.accounts.multisig.used_nonces.contains(&nonce),
-            ErrorCode::NonceAlreadyUsed
-        );
-
-        // Validate instruction limits
-       require!(
-        accounts.len() <= MAX_INSTRUCTION_ACCOUNTS,
-        ErrorCode::TooManyAccounts
-       );
-
-       require!(
-        data.len() <= MAX_INSTRUCTION_DATA_SIZE,
-        ErrorCode::InstructionDataTooLarge
-       );
+        // Proposer-supplied and untrusted, only the length is checked
+        let description = description.unwrap_or_default();
+        require!(description.len() <= MAX_DESCRIPTION_LEN, ErrorCode::DescriptionTooLong);
 
         // Optional: Handle system nonce if needed
         if let Some(nonce_account) = &ctx.accounts.nonce_account {
-            // Validate nonce authority if needed
-            let nonce_account_data = nonce_account.try_borrow_data()
-                .map_err(|_| ErrorCode::InvalidNonceAuthority)?;
-            
-            // Simple validation without full deserialization
-            // The nonce account authority is at offset 40 (after version, state, and reserved)
-            if nonce_account_data.len() >= 72 {
-                let authority_bytes = &nonce_account_data[40..72];
-                let authority = Pubkey::try_from(authority_bytes)
-                    .map_err(|_| ErrorCode::InvalidNonceAuthority)?;
-                
-                require_keys_eq!(
-                    authority,
-                    ctx.accounts.multisig.key(),
-                    ErrorCode::InvalidNonceAuthority
-                );
-            }
+            // Must be an initialized system nonce account whose authority is the multisig
+            let authority = nonce_authority(nonce_account)?;
+            require_keys_eq!(
+                authority,
+                ctx.accounts.multisig.key(),
+                ErrorCode::InvalidNonceAuthority
+            );
+
+            let recent_blockhashes = ctx
+                .accounts
+                .recent_blockhashes
+                .as_ref()
+                .ok_or(ErrorCode::InvalidNonceAccount)?;
 
             let ix = system_instruction::advance_nonce_account(
                 &nonce_account.key(),
@@ -108,7 +293,7 @@ pub mod multisig {
             let multisig_seeds: &[&[u8]] = &[
                 b"multisig",
                 &ctx.accounts.multisig.multisig_id.to_le_bytes(),
-                &[ctx.bumps.multisig]
+                &[ctx.accounts.multisig.bump]
             ];
             
             invoke_signed(
@@ -116,293 +301,6035 @@ pub mod multisig {
                 &[
                     nonce_account.to_account_info(),
                     ctx.accounts.multisig.to_account_info(),
-                    ctx.accounts.recent_blockhashes.as_ref().unwrap().to_account_info(),
+                    recent_blockhashes.to_account_info(),
                 ],
                 &[multisig_seeds],
             )?;
         }
 
         // Now get mutable references after all immutable operations are done
-        let multisig = &mut ctx.accounts.multisig;
+        record_proposal(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            proposer.key(),
+            nonce,
+            instructions,
+            ProposalTerms {
+                executor_reward,
+                expires_at,
+                depends_on,
+                required_approvals,
+                valid_from_slot,
+                valid_until_slot,
+                description,
+                lookup_keys_hash,
+            },
+        )?;
         let transaction = &mut ctx.accounts.transaction;
+        transaction.priority_hint = priority_hint.unwrap_or(PriorityHint::Low);
+        debug_assert_fits(transaction);
 
-        transaction.multisig = multisig.key();
-        transaction.proposer = proposer.key();
-        transaction.approvals = Vec::new();
-        transaction.did_execute = false;
-        transaction.nonce = nonce;
-        
-        transaction.program_id = program_id;
-        transaction.accounts = accounts;
-        transaction.data = data;
+        Ok(CreatedTransaction {
+            transaction: ctx.accounts.transaction.key(),
+            bump: ctx.bumps.transaction,
+        })
+    }
 
-        // Store used nonce with size limit
-        if multisig.used_nonces.len() >= MAX_STORED_NONCES {
-            multisig.used_nonces.remove(0);
+    // Like create_transaction (without durable nonce support), but safe to retry
+    // after an ambiguous timeout: if the proposal already exists with the same
+    // proposer and tx_hash this succeeds without changing anything.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_transaction_idempotent(
+        ctx: Context<CreateTransactionIdempotent>,
+        _multisig_id: u64,
+        nonce: u64,
+        instructions: Vec<TransactionInstruction>,
+        expires_at: i64,
+        executor_reward: u64,
+        depends_on: Option<u64>,
+        priority_hint: Option<PriorityHint>,
+        description: Option<String>,
+        valid_from_slot: u64,
+        valid_until_slot: u64,
+        required_approvals: u8,
+    ) -> Result<CreatedTransaction> {
+        let proposer = ctx.accounts.proposer.key();
+        let created = CreatedTransaction {
+            transaction: ctx.accounts.transaction.key(),
+            bump: ctx.bumps.transaction,
+        };
+
+        let description = description.unwrap_or_default();
+
+        // record_proposal always sets the multisig, so a default one means the
+        // account was just created by init_if_needed
+        let existing = &ctx.accounts.transaction;
+        if existing.multisig != Pubkey::default() {
+            // A default expiry was resolved against the clock of the first attempt
+            let expires_at = if expires_at == 0 && ctx.accounts.multisig.default_tx_ttl > 0 {
+                existing.expires_at
+            } else {
+                expires_at
+            };
+            let terms = ProposalTerms {
+                executor_reward,
+                expires_at,
+                depends_on,
+                required_approvals,
+                valid_from_slot,
+                valid_until_slot,
+                description,
+                lookup_keys_hash: hash_lookup_keys(&instructions, ctx.remaining_accounts)?,
+            };
+            let tx_hash = hash_proposal(&instructions, &terms)?;
+            if existing.proposer != proposer || existing.tx_hash != tx_hash {
+                msg!(
+                    "nonce {} holds a different proposal: tx_hash {:?} by {}, requested {:?} by {}",
+                    nonce,
+                    existing.tx_hash,
+                    existing.proposer,
+                    tx_hash,
+                    proposer
+                );
+                return Err(ErrorCode::NonceAlreadyUsed.into());
+            }
+            return Ok(created);
+        }
+
+        validate_proposal(&ctx.accounts.multisig, &proposer, nonce, &instructions, expires_at)?;
+        validate_pda_signers(&ctx.accounts.multisig.key(), &instructions, ctx.program_id)?;
+        validate_slot_window(valid_from_slot, valid_until_slot)?;
+        validate_required_approvals(&ctx.accounts.multisig, required_approvals)?;
+        require!(executor_reward <= MAX_EXECUTOR_REWARD, ErrorCode::ExecutorRewardTooHigh);
+        let lookup_keys_hash = hash_lookup_keys(&instructions, ctx.remaining_accounts)?;
+
+        require!(description.len() <= MAX_DESCRIPTION_LEN, ErrorCode::DescriptionTooLong);
+
+        record_proposal(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            proposer,
+            nonce,
+            instructions,
+            ProposalTerms {
+                executor_reward,
+                expires_at,
+                depends_on,
+                required_approvals,
+                valid_from_slot,
+                valid_until_slot,
+                description,
+                lookup_keys_hash,
+            },
+        )?;
+        ctx.accounts.transaction.priority_hint = priority_hint.unwrap_or(PriorityHint::Low);
+
+        Ok(created)
+    }
+
+    // Creates up to MAX_BATCH_SIZE single-instruction proposals at once, e.g. a
+    // payroll run. Their transaction PDAs are passed as writable remaining_accounts
+    // in the same order and created here, paid by the proposer. Each proposal goes
+    // through the same checks as create_transaction and emits its own TransactionCreated.
+    pub fn create_transactions_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateTransactionsBatch<'info>>,
+        _multisig_id: u64,
+        proposals: Vec<BatchProposal>,
+    ) -> Result<()> {
+        let proposer = ctx.accounts.proposer.key();
+        let multisig_key = ctx.accounts.multisig.key();
+
+        require!(proposals.len() <= MAX_BATCH_SIZE, ErrorCode::BatchTooLarge);
+        require!(proposals.len() == ctx.remaining_accounts.len(), ErrorCode::AccountMismatch);
+
+        let space = Transaction::space(ctx.accounts.multisig.max_data_size as usize);
+        for (proposal, info) in proposals.into_iter().zip(ctx.remaining_accounts.iter()) {
+            let nonce_bytes = proposal.nonce.to_le_bytes();
+            let (expected, bump) = Pubkey::find_program_address(
+                &[b"transaction", multisig_key.as_ref(), &nonce_bytes],
+                ctx.program_id,
+            );
+            require_keys_eq!(*info.key, expected, ErrorCode::AccountMismatch);
+
+            let instructions = vec![TransactionInstruction {
+                program_id: proposal.program_id,
+                accounts: proposal.accounts,
+                data: proposal.data,
+            }];
+            validate_proposal(&ctx.accounts.multisig, &proposer, proposal.nonce, &instructions, 0)?;
+            validate_pda_signers(&multisig_key, &instructions, ctx.program_id)?;
+            // remaining_accounts only hold transaction PDAs, so lookup references fail here
+            let lookup_keys_hash = hash_lookup_keys(&instructions, &[])?;
+
+            rent_funded_space(&ctx.accounts.proposer, space)?;
+            let seeds: &[&[u8]] = &[b"transaction", multisig_key.as_ref(), &nonce_bytes, &[bump]];
+            create_pda_account(info, space, &crate::ID, &ctx.accounts.proposer, &ctx.accounts.system_program, seeds)?;
+            info.try_borrow_mut_data()?[..8].copy_from_slice(Transaction::DISCRIMINATOR);
+
+            let mut transaction = Account::<Transaction>::try_from(info)?;
+            record_proposal(
+                &mut ctx.accounts.multisig,
+                &mut transaction,
+                proposer,
+                proposal.nonce,
+                instructions,
+                ProposalTerms {
+                    lookup_keys_hash,
+                    ..ProposalTerms::default()
+                },
+            )?;
+            transaction.exit(ctx.program_id)?;
         }
-        multisig.used_nonces.push(nonce);
 
-     // Emit event
-     emit!(TransactionCreated {
-      multisig: multisig.key(),
-      transaction: transaction.key(),
-      proposer: proposer.key(),
-      nonce,
-     });
-        
         Ok(())
     }
 
-    pub fn approve_transaction(ctx: Context<ApproveTransaction>, _multisig_id: u64, _nonce: u64) -> Result<()> {
-        let owner = ctx.accounts.owner.key();
-        let multisig = &ctx.accounts.multisig;
+    // Proposes a single instruction and records the proposer's approval in one call,
+    // saving the separate approve_transaction round trip
+    pub fn create_and_approve(
+        ctx: Context<CreateAndApprove>,
+        _multisig_id: u64,
+        nonce: u64,
+        program_id: Pubkey,
+        accounts: Vec<TransactionAccount>,
+        data: Vec<u8>,
+    ) -> Result<CreatedTransaction> {
+        let proposer = ctx.accounts.proposer.key();
+        let instructions = vec![TransactionInstruction { program_id, accounts, data }];
+
+        validate_proposal(&ctx.accounts.multisig, &proposer, nonce, &instructions, 0)?;
+        validate_pda_signers(&ctx.accounts.multisig.key(), &instructions, ctx.program_id)?;
+        let lookup_keys_hash = hash_lookup_keys(&instructions, ctx.remaining_accounts)?;
+        record_proposal(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            proposer,
+            nonce,
+            instructions,
+            ProposalTerms {
+                lookup_keys_hash,
+                ..ProposalTerms::default()
+            },
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        record_approval(&mut ctx.accounts.multisig, &mut ctx.accounts.transaction, proposer, now);
+
+        Ok(CreatedTransaction {
+            transaction: ctx.accounts.transaction.key(),
+            bump: ctx.bumps.transaction,
+        })
+    }
+
+    // Packages a system transfer out of the vault PDA into a regular proposal
+    pub fn propose_sol_transfer(
+        ctx: Context<ProposeSolTransfer>,
+        _multisig_id: u64,
+        nonce: u64,
+        recipient: Pubkey,
+        amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let proposer = ctx.accounts.proposer.key();
+        let vault = ctx.accounts.vault.key();
+
+        require_keys_neq!(recipient, vault, ErrorCode::SelfTransferNotAllowed);
+        require!(
+            ctx.accounts.multisig.recipient_allowed(&[recipient]),
+            ErrorCode::RecipientNotAllowed
+        );
+
+        let instructions = vec![system_instruction::transfer(&vault, &recipient, amount).into()];
+
+        validate_proposal(&ctx.accounts.multisig, &proposer, nonce, &instructions, expires_at)?;
+        record_proposal(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            proposer,
+            nonce,
+            instructions,
+            ProposalTerms {
+                expires_at,
+                ..ProposalTerms::default()
+            },
+        )?;
+
+        emit!(SolTransferProposed {
+            multisig: ctx.accounts.multisig.key(),
+            transaction: ctx.accounts.transaction.key(),
+            vault,
+            recipient,
+            amount,
+            event_seq: ctx.accounts.multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Packages an SPL token transfer, authorized by the multisig PDA, into a regular proposal
+    pub fn propose_token_transfer(
+        ctx: Context<ProposeTokenTransfer>,
+        _multisig_id: u64,
+        nonce: u64,
+        amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let proposer = ctx.accounts.proposer.key();
+        let multisig_key = ctx.accounts.multisig.key();
+        let mint = ctx.accounts.mint.key();
+        let source = ctx.accounts.source.key();
+        let destination = ctx.accounts.destination.key();
+        require_keys_neq!(source, destination, ErrorCode::SelfTransferNotAllowed);
+
+        // Token account layout: mint (0..32), owner (32..64)
+        {
+            let source_data = ctx.accounts.source.try_borrow_data()?;
+            require!(source_data.len() >= 64, ErrorCode::InvalidTokenAccount);
+            require!(source_data[0..32] == mint.to_bytes(), ErrorCode::InvalidTokenAccount);
+            require!(source_data[32..64] == multisig_key.to_bytes(), ErrorCode::InvalidTokenAccount);
+        }
+
+        // Either the token account itself or its owner may be allowlisted
+        {
+            let destination_data = ctx.accounts.destination.try_borrow_data()?;
+            require!(destination_data.len() >= 64, ErrorCode::InvalidTokenAccount);
+            let destination_owner = Pubkey::try_from(&destination_data[32..64]).unwrap();
+            require!(
+                ctx.accounts.multisig.recipient_allowed(&[destination, destination_owner]),
+                ErrorCode::RecipientNotAllowed
+            );
+        }
+
+        // Mint layout: decimals at offset 44
+        let decimals = {
+            let mint_data = ctx.accounts.mint.try_borrow_data()?;
+            require!(mint_data.len() >= 82, ErrorCode::InvalidTokenAccount);
+            mint_data[44]
+        };
+
+        let instructions = vec![token_transfer_checked(
+            &source,
+            &mint,
+            &destination,
+            &multisig_key,
+            amount,
+            decimals,
+        )];
+
+        validate_proposal(&ctx.accounts.multisig, &proposer, nonce, &instructions, expires_at)?;
+        record_proposal(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            proposer,
+            nonce,
+            instructions,
+            ProposalTerms {
+                expires_at,
+                ..ProposalTerms::default()
+            },
+        )?;
+
+        emit!(TokenTransferProposed {
+            multisig: multisig_key,
+            transaction: ctx.accounts.transaction.key(),
+            mint,
+            source,
+            destination,
+            amount,
+            event_seq: ctx.accounts.multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // tx_hash must match the stored digest, binding the approval to the exact payload the owner reviewed
+    pub fn approve_transaction(
+        ctx: Context<ApproveTransaction>,
+        _multisig_id: u64,
+        _nonce: u64,
+        tx_hash: [u8; 32],
+        memo: Option<[u8; 32]>,
+        expected_owner_count: u8,
+    ) -> Result<()> {
+        let signer = ctx.accounts.owner.key();
+        let multisig = &mut ctx.accounts.multisig;
         let transaction = &mut ctx.accounts.transaction;
 
+        // A delegate approves on behalf of its owner, who is what gets recorded
+        let owner = multisig.owner_for_delegate(&signer).unwrap_or(signer);
+
         // Check if signer is an owner
         if !multisig.owners.contains(&owner) {
             return Err(ErrorCode::NotOwner.into());
         }
 
         // Check if already approved
-        if transaction.approvals.contains(&owner) {
+        if transaction.has_approved(multisig, &owner) {
             return Err(ErrorCode::AlreadyApproved.into());
         }
 
         // Check if transaction is already executed
         require!(!transaction.did_execute, ErrorCode::AlreadyExecuted);
 
-        // Add approval
-        transaction.approvals.push(owner);
-        
-        // Emit event
-    emit!(TransactionApproved {
-      transaction: transaction.key(),
-      approver: owner,
-      approvals_count: transaction.approvals.len() as u8,
-      threshold: multisig.threshold,
-     });
+        require!(!transaction.rejected, ErrorCode::TransactionRejected);
 
-    Ok(())
-    }
+        require!(transaction.tx_hash == tx_hash, ErrorCode::HashMismatch);
 
-    pub fn execute_transaction(ctx: Context<ExecuteTransaction>, multisig_id: u64, _nonce: u64) -> Result<()> {
-        let multisig = &ctx.accounts.multisig;
-        let transaction = &mut ctx.accounts.transaction;
+        // Opt-in pin to the membership the approver reviewed against, 0 skips it
+        require!(
+            expected_owner_count == 0 || multisig.owners.len() == expected_owner_count as usize,
+            ErrorCode::OwnerSetChanged
+        );
 
-        // Check if already executed
-        require!(!transaction.did_execute, ErrorCode::AlreadyExecuted);
+        // No point approving something that can no longer execute
+        let now = Clock::get()?.unix_timestamp;
+        require!(!transaction.is_expired(now), ErrorCode::TransactionExpired);
 
-        // Check if enough approvals
         require!(
-            transaction.approvals.len() >= multisig.threshold as usize,
-            ErrorCode::NotEnoughApprovals
-        );
-
-        // Mark as executed
-        transaction.did_execute = true;
-
-        // Fix: Create proper seeds array
-        let multisig_seeds: &[&[u8]] = &[
-         b"multisig",
-         &multisig_id.to_le_bytes(),
-         &[ctx.bumps.multisig],
-        ];
-
-        // Build the instruction from stored data
-      let instruction = anchor_lang::solana_program::instruction::Instruction {
-      program_id: transaction.program_id,
-      accounts: transaction.accounts.iter().map(|acc| {
-          anchor_lang::solana_program::instruction::AccountMeta {
-            pubkey: acc.pubkey,
-            is_signer: acc.is_signer,
-            is_writable: acc.is_writable,
-         }
-       }).collect(),
-       data: transaction.data.clone(),
-    };
+            !transaction.vote_too_soon(multisig.min_vote_spacing, now),
+            ErrorCode::VotingTooFast
+        );
+
+        multisig.last_activity = now;
 
-// Execute the instruction using Cross Program Invocation (CPI)
-anchor_lang::solana_program::program::invoke_signed(
-       &instruction,
-        &ctx.remaining_accounts,
-       &[multisig_seeds]
-      )?;
+        if let Some(memo) = memo {
+            if transaction.memos.len() >= MAX_APPROVAL_MEMOS {
+                transaction.memos.remove(0);
+            }
+            transaction.memos.push(ApprovalMemo { owner, memo });
+        }
 
-        // Clear transaction data after execution to free up space
-      transaction.data.clear();
-      transaction.accounts.clear();
+        record_approval(multisig, transaction, owner, now);
 
-      // Emit event
-    emit!(TransactionExecuted {
-      transaction: transaction.key(),
-      executor: ctx.accounts.executor.key(),
-    });
-        
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(multisig_id: u64)]
-pub struct Initialize<'info> {
-    #[account(
-        init, 
-        payer = creator, 
-        space = 8 +                           // discriminator
-                4 + (32 * MAX_OWNERS) +       // owners vec
-                1 +                           // threshold
-                32 +                          // creator
-                8 +                           // multisig_id
-                4 + (8 * MAX_STORED_NONCES),  // used_nonces vec
-        seeds = [b"multisig", &multisig_id.to_le_bytes()],
-        bump
-    )]
-    pub multisig: Account<'info, Multisig>,
-    #[account(mut)]
-    pub creator: Signer<'info>,
-    pub system_program: Program<'info, System>
-}
+    // Approves several pending transactions at once. The transaction PDAs are passed
+    // as remaining accounts in the same order as nonces and tx_hashes. Transactions
+    // that are executed, expired, already approved by the caller or still within
+    // min_vote_spacing of their last approval are skipped.
+    // Returns the number of new approvals.
+    pub fn approve_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ApproveMany<'info>>,
+        _multisig_id: u64,
+        nonces: Vec<u64>,
+        tx_hashes: Vec<[u8; 32]>,
+    ) -> Result<u8> {
+        let owner = ctx.accounts.owner.key();
+        let multisig = &mut ctx.accounts.multisig;
+        let multisig_key = multisig.key();
 
-#[derive(Accounts)]
-#[instruction(multisig_id: u64, nonce: u64)]
-pub struct CreateTransaction<'info> {
-    #[account(mut)]
-    pub proposer: Signer<'info>,
+        require!(multisig.owners.contains(&owner), ErrorCode::NotOwner);
 
-    #[account(
-        mut,
-        seeds = [b"multisig", &multisig_id.to_le_bytes()],
-        bump,
-    )]
-    pub multisig: Account<'info, Multisig>,
+        require!(nonces.len() <= MAX_BATCH_SIZE, ErrorCode::BatchTooLarge);
+        require!(
+            nonces.len() == tx_hashes.len() && nonces.len() == ctx.remaining_accounts.len(),
+            ErrorCode::AccountMismatch
+        );
 
-    #[account(
-        init,
-        payer = proposer,
-        space = 8 +                           // discriminator
-        32 +                          // multisig
-        32 +                          // proposer  
-        4 + (32 * MAX_OWNERS) +       // approvals vec
-        1 +                           // did_execute
-        8 +                           // nonce
-        32 +                          // program_id
-        4 + (65 * 10) +               // accounts vec (max 10 accounts, 65 bytes each)
-        4 + 1024,                     // data vec (max 1024 bytes)
-        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
-        bump
-    )]
-    pub transaction: Account<'info, Transaction>,
+        let now = Clock::get()?.unix_timestamp;
+        let mut approved = 0u8;
 
-    /// CHECK: Optional system nonce account
-    pub nonce_account: Option<AccountInfo<'info>>,
+        for ((nonce, tx_hash), info) in nonces.iter().zip(tx_hashes.iter()).zip(ctx.remaining_accounts.iter()) {
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"transaction", multisig_key.as_ref(), &nonce.to_le_bytes()],
+                ctx.program_id,
+            );
+            require_keys_eq!(*info.key, expected, ErrorCode::AccountMismatch);
 
-    /// CHECK: Sysvar required by nonce account (optional)
-    pub recent_blockhashes: Option<Sysvar<'info, RecentBlockhashes>>,
+            let mut transaction = Account::<Transaction>::try_from(info)?;
 
-    pub system_program: Program<'info, System>,
-}
+            if transaction.did_execute
+                || transaction.rejected
+                || transaction.is_expired(now)
+                || transaction.has_approved(multisig, &owner)
+                || transaction.vote_too_soon(multisig.min_vote_spacing, now)
+            {
+                continue;
+            }
 
-#[derive(Accounts)]
-#[instruction(multisig_id: u64, nonce: u64)]
-pub struct ApproveTransaction<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+            require!(transaction.tx_hash == *tx_hash, ErrorCode::HashMismatch);
 
-    #[account(
-        seeds = [b"multisig", &multisig_id.to_le_bytes()],
-        bump,
-    )]
-    pub multisig: Account<'info, Multisig>,
+            record_approval(multisig, &mut transaction, owner, now);
+            transaction.exit(ctx.program_id)?;
+            approved += 1;
+        }
 
-    #[account(
-        mut,
-        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
-        bump,
-    )]
-    pub transaction: Account<'info, Transaction>,
-}
+        if approved > 0 {
+            multisig.last_activity = now;
+        }
 
-// Fix: Remove the problematic remaining_accounts field from the struct
-#[derive(Accounts)]
-#[instruction(multisig_id: u64, nonce: u64)]
-pub struct ExecuteTransaction<'info> {
-    #[account(mut)]
-    pub executor: Signer<'info>,
+        Ok(approved)
+    }
 
-    #[account(
-        seeds = [b"multisig", &multisig_id.to_le_bytes()],
-        bump,
-    )]
-    pub multisig: Account<'info, Multisig>,
+    // Dropping back below the threshold also clears a started timelock, so
+    // execution needs the threshold reached again and a fresh delay, unless
+    // the multisig has persist_timelock_on_revoke set
+    pub fn revoke_approval(ctx: Context<RevokeApproval>, _multisig_id: u64, _nonce: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let multisig = &mut ctx.accounts.multisig;
+        let transaction = &mut ctx.accounts.transaction;
 
-    #[account(
-        mut,
-        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
-        bump,
-    )]
-    pub transaction: Account<'info, Transaction>,
-    // remaining_accounts are accessed via ctx.remaining_accounts in the function
-}
+        // Check if transaction is already executed
+        require!(!transaction.did_execute, ErrorCode::AlreadyExecuted);
 
-#[account]
-pub struct Multisig {
-    pub owners: Vec<Pubkey>,
-    pub threshold: u8,
-    pub creator: Pubkey,
-    pub multisig_id: u64,
-    pub used_nonces: Vec<u64>,
-}
+        require!(transaction.has_approved(multisig, &owner), ErrorCode::ApprovalNotFound);
+        transaction.clear_approval(multisig, &owner);
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct TransactionAccount {
-    pub pubkey: Pubkey,
-    pub is_signer: bool,
-    pub is_writable: bool,
-}
+        emit!(ApprovalRevoked {
+            transaction: transaction.key(),
+            owner,
+            approvals_count: transaction.approvals_count(multisig),
+            event_seq: multisig.next_event_seq(),
+        });
 
-#[account]
-pub struct Transaction {
-    pub multisig: Pubkey,
-    pub proposer: Pubkey,
-    pub approvals: Vec<Pubkey>,
-    pub did_execute: bool,
-    pub nonce: u64,
-    pub program_id: Pubkey,
-    pub accounts: Vec<TransactionAccount>,
-    pub data: Vec<u8>,
-}
+        if !multisig.persist_timelock_on_revoke
+            && transaction.eta != 0
+            && !multisig.threshold_met_for(transaction, &transaction.approvers(multisig))
+        {
+            transaction.eta = 0;
 
-#[event]
-pub struct TransactionCreated {
-    pub multisig: Pubkey,
-    pub transaction: Pubkey,
-    pub proposer: Pubkey,
-    pub nonce: u64,
-}
+            emit!(TransactionDequeued {
+                transaction: transaction.key(),
+                approvals_count: transaction.approvals_count(multisig),
+                event_seq: multisig.next_event_seq(),
+            });
+        }
 
-#[event]
-pub struct TransactionApproved {
-    pub transaction: Pubkey,
-    pub approver: Pubkey,
-    pub approvals_count: u8,
-    pub threshold: u8,
-}
+        Ok(())
+    }
 
-#[event]
-pub struct TransactionExecuted {
-    pub transaction: Pubkey,
-    pub executor: Pubkey,
-}
+    // Records a vote against the proposal. Once enough owners reject that the
+    // threshold can no longer be reached, the transaction is marked as rejected
+    // and can never execute. Rejecting withdraws any approval by the same owner.
+    pub fn reject_transaction(ctx: Context<RejectTransaction>, _multisig_id: u64, _nonce: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let multisig = &ctx.accounts.multisig;
+        let transaction = &mut ctx.accounts.transaction;
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Invalid threshold")]
-    InvalidThreshold,
-    #[msg("Duplicate owners")]
-    DuplicateOwners,
-    #[msg("No owner provided")]
-    NoOwners,
-    #[msg("The proposer is not an owner")]
+        require!(multisig.owners.contains(&owner), ErrorCode::NotOwner);
+        require!(!transaction.did_execute, ErrorCode::AlreadyExecuted);
+        require!(!transaction.rejections.contains(&owner), ErrorCode::AlreadyRejected);
+
+        transaction.clear_approval(multisig, &owner);
+        transaction.rejections.push(owner);
+
+        // Dead once the remaining weight can't cover the threshold
+        let rejection_weight = multisig.approval_weight(&transaction.rejections);
+        if rejection_weight > multisig.total_weight().saturating_sub(multisig.required_weight_for(transaction)) {
+            transaction.rejected = true;
+        }
+
+        // A single veto owner kills it outright, however many approvals it has
+        if multisig.veto_owners.contains(&owner) && !transaction.vetoed {
+            transaction.vetoed = true;
+            transaction.rejected = true;
+
+            emit!(TransactionVetoed {
+                transaction: transaction.key(),
+                veto_owner: owner,
+                event_seq: ctx.accounts.multisig.next_event_seq(),
+            });
+        }
+
+        emit!(TransactionRejectionRecorded {
+            transaction: transaction.key(),
+            owner,
+            rejections_count: transaction.rejections.len() as u8,
+            rejected: transaction.rejected,
+            event_seq: ctx.accounts.multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Runs `count` instructions starting at `start_index`, which must be the
+    // number already executed. Large batches can be split across calls to stay
+    // within the compute limit; pass count = 0 to run everything that's left.
+    // The returned outcome tells a finished batch from a chunk or a held outflow.
+    pub fn execute_transaction(
+        ctx: Context<ExecuteTransaction>,
+        _multisig_id: u64,
+        _nonce: u64,
+        start_index: u8,
+        count: u8,
+    ) -> Result<ExecutionOutcome> {
+        let approvals = ctx.accounts.transaction.approvers(&ctx.accounts.multisig);
+        let start = start_index as usize;
+        let end = match count {
+            0 => ctx.accounts.transaction.instructions.len(),
+            count => start + count as usize,
+        };
+
+        execute_approved(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            &ctx.accounts.executor,
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program,
+            ctx.remaining_accounts,
+            ctx.bumps.vault,
+            &approvals,
+            start..end,
+        )
+    }
+
+    // Executes with owner signatures gathered off-chain instead of (or on top of)
+    // on-chain approvals. Each signature must be verified by an ed25519 precompile
+    // instruction earlier in the same transaction; the signed message binds the
+    // multisig, nonce and tx_hash so it can't be replayed against another proposal.
+    pub fn execute_with_signatures(
+        ctx: Context<ExecuteWithSignatures>,
+        _multisig_id: u64,
+        nonce: u64,
+        sigs: Vec<Ed25519Approval>,
+    ) -> Result<ExecutionOutcome> {
+        require!(sigs.len() <= MAX_OWNERS, ErrorCode::TooManyOwners);
+
+        let message = build_approval_message(
+            &ctx.accounts.multisig.key(),
+            nonce,
+            &ctx.accounts.transaction.tx_hash,
+        );
+
+        // Always runs whatever is left of the batch
+        let transaction = &ctx.accounts.transaction;
+        let chunk = transaction.executed_count as usize..transaction.instructions.len();
+
+        let mut approvals = ctx.accounts.transaction.approvers(&ctx.accounts.multisig);
+        for sig in &sigs {
+            require!(
+                ctx.accounts.multisig.owners.contains(&sig.owner),
+                ErrorCode::NotOwner
+            );
+            require!(
+                ed25519_verified(&ctx.accounts.instructions, sig, &message)?,
+                ErrorCode::InvalidSignature
+            );
+
+            // Owners who also approved on-chain are only counted once
+            if !approvals.contains(&sig.owner) {
+                approvals.push(sig.owner);
+            }
+        }
+
+        // Signatures aren't stored as approvals, so under a timelock the first
+        // call that meets the threshold only starts it, returning Queued so the
+        // eta persists; the same signatures execute once min_delay has passed
+        let multisig = &ctx.accounts.multisig;
+        let transaction = &mut ctx.accounts.transaction;
+        if multisig.min_delay > 0 && transaction.eta == 0 && multisig.threshold_met_for(transaction, &approvals) {
+            transaction.eta = Clock::get()?.unix_timestamp;
+
+            emit!(TransactionQueued {
+                transaction: transaction.key(),
+                eta: transaction.eta,
+                executable_at: transaction.eta + multisig.min_delay,
+                event_seq: ctx.accounts.multisig.next_event_seq(),
+            });
+
+            return Ok(ExecutionOutcome::Queued);
+        }
+
+        execute_approved(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            &ctx.accounts.executor,
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program,
+            ctx.remaining_accounts,
+            ctx.bumps.vault,
+            &approvals,
+            chunk,
+        )
+    }
+
+    // Runs whatever is left of the batch and closes the transaction account to
+    // the proposer in the same instruction. A failing CPI rolls back both.
+    pub fn execute_and_close(ctx: Context<ExecuteAndClose>, _multisig_id: u64, _nonce: u64) -> Result<ExecutionOutcome> {
+        let approvals = ctx.accounts.transaction.approvers(&ctx.accounts.multisig);
+        let transaction = &ctx.accounts.transaction;
+        let chunk = transaction.executed_count as usize..transaction.instructions.len();
+
+        // Open dependents need the executed account to check did_execute
+        require!(
+            !ctx.accounts.multisig.pinned_nonces.contains(&transaction.nonce),
+            ErrorCode::TransactionPinned
+        );
+
+        // Placing a hold returns early and the account would still be closed, so a
+        // large outflow has to be held through execute_transaction and confirmed first
+        require!(
+            transaction.executed_count > 0
+                || !ctx.accounts.multisig.outflow_needs_confirmation(transaction, ctx.accounts.vault.lamports()),
+            ErrorCode::LargeOutflowNotConfirmed
+        );
+
+        let outcome = execute_approved(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            &ctx.accounts.executor,
+            &ctx.accounts.vault,
+            &ctx.accounts.system_program,
+            ctx.remaining_accounts,
+            ctx.bumps.vault,
+            &approvals,
+            chunk,
+        )?;
+
+        emit!(TransactionClosed {
+            transaction: ctx.accounts.transaction.key(),
+            proposer: ctx.accounts.proposer.key(),
+            event_seq: ctx.accounts.multisig.next_event_seq(),
+        });
+
+        Ok(outcome)
+    }
+
+    // Read-only; meant to be simulated so clients don't re-implement the execution rules
+    pub fn transaction_state(ctx: Context<TransactionView>, _multisig_id: u64, _nonce: u64) -> Result<TransactionState> {
+        let multisig = &ctx.accounts.multisig;
+        let transaction = &ctx.accounts.transaction;
+        let now = Clock::get()?.unix_timestamp;
+
+        let is_expired = transaction.is_expired(now);
+        let executable = !transaction.did_execute
+            && !transaction.rejected
+            && !transaction.execution_expired(multisig.expiry_grace, now)
+            && multisig.threshold_met_for(transaction, &transaction.approvers(multisig))
+            && multisig.role_quorum_met(&transaction.approvers(multisig))
+            && transaction.within_slot_window(Clock::get()?.slot)
+            && transaction.timelock_elapsed(multisig.min_delay, now);
+
+        Ok(TransactionState {
+            approvals_count: transaction.approvals_count(multisig),
+            threshold: multisig.required_weight_for(transaction),
+            did_execute: transaction.did_execute,
+            is_expired,
+            executable,
+        })
+    }
+
+    // Dry run of execute_transaction's account handling against the supplied
+    // remaining_accounts, without invoking anything. Reports the first problem found.
+    pub fn simulate_transaction(ctx: Context<TransactionView>, _multisig_id: u64, _nonce: u64) -> Result<SimulationResult> {
+        let transaction = &ctx.accounts.transaction;
+        if transaction.did_execute {
+            return Ok(SimulationResult::new(SimulationCode::AlreadyExecuted, 0, transaction.key()));
+        }
+
+        let multisig_key = ctx.accounts.multisig.key();
+        let (vault_key, _) = Pubkey::find_program_address(&[b"vault", multisig_key.as_ref()], ctx.program_id);
+
+        let executed = transaction.executed_count as usize;
+        for (index, ix) in transaction.instructions.iter().enumerate().skip(executed) {
+            if let Some((code, account)) =
+                simulate_instruction(ix, ctx.remaining_accounts, &[multisig_key, vault_key])
+            {
+                return Ok(SimulationResult::new(code, index as u8, account));
+            }
+        }
+
+        Ok(SimulationResult::new(SimulationCode::Ok, 0, Pubkey::default()))
+    }
+
+    // Off-chain hint for how urgently a proposal should be reviewed; not enforced
+    pub fn transaction_priority(ctx: Context<TransactionView>, _multisig_id: u64, _nonce: u64) -> Result<PriorityHint> {
+        Ok(ctx.accounts.transaction.priority_hint)
+    }
+
+    // The exact bytes an owner signs to approve this proposal off-chain, see
+    // execute_with_signatures. Lets signing devices and tooling agree on the format.
+    pub fn approval_message(ctx: Context<TransactionView>, _multisig_id: u64, _nonce: u64) -> Result<Vec<u8>> {
+        Ok(build_approval_message(
+            &ctx.accounts.multisig.key(),
+            ctx.accounts.transaction.nonce,
+            &ctx.accounts.transaction.tx_hash,
+        ))
+    }
+
+    // Read-only summary for dashboards that batch-simulate many multisigs
+    pub fn multisig_summary(ctx: Context<MultisigView>, _multisig_id: u64) -> Result<MultisigSummary> {
+        let multisig = &ctx.accounts.multisig;
+
+        Ok(MultisigSummary {
+            owner_count: multisig.owners.len() as u8,
+            threshold: multisig.threshold,
+            used_nonce_count: multisig.used_nonces.len() as u16,
+            paused: multisig.paused,
+            created_at: multisig.created_at,
+            roles: multisig.roles.clone(),
+        })
+    }
+
+    // Whether the current owners can still reach the threshold and role quorum at all. Monitoring
+    // can use it to warn before a wallet becomes permanently stuck.
+    pub fn health_check(ctx: Context<MultisigView>, _multisig_id: u64) -> Result<HealthReport> {
+        let multisig = &ctx.accounts.multisig;
+        let required_weight = multisig.required_weight();
+        let total_weight = multisig.total_weight();
+        let role_quorum_reachable = multisig.role_quorum_reachable();
+
+        Ok(HealthReport {
+            live: required_weight > 0 && required_weight <= total_weight && role_quorum_reachable,
+            required_weight,
+            total_weight,
+            role_quorum_reachable,
+        })
+    }
+
+    // Membership check for clients deciding whether to offer an approve action
+    pub fn is_owner(ctx: Context<MultisigView>, _multisig_id: u64, candidate: Pubkey) -> Result<OwnerStatus> {
+        let multisig = &ctx.accounts.multisig;
+        let index = multisig.owners.iter().position(|owner| *owner == candidate);
+
+        Ok(OwnerStatus {
+            is_owner: index.is_some(),
+            weight: index.and_then(|index| multisig.weights.get(index).copied()),
+        })
+    }
+
+    // Read-only and account-free, so SDKs and explorers can map nonces to
+    // transaction addresses without hardcoding the seeds
+    pub fn derive_transaction_address(
+        ctx: Context<DeriveAddress>,
+        multisig: Pubkey,
+        nonce: u64,
+    ) -> Result<TransactionAddress> {
+        let (address, bump) = Pubkey::find_program_address(
+            &[TRANSACTION_SEED, multisig.as_ref(), &nonce.to_le_bytes()],
+            ctx.program_id,
+        );
+
+        Ok(TransactionAddress { address, bump })
+    }
+
+    // Does nothing; its arguments spell out the transaction PDA seeds in the
+    // IDL: TRANSACTION_SEED, the multisig address, then the nonce as 8
+    // little-endian bytes
+    pub fn transaction_address_seeds(
+        _ctx: Context<DeriveAddress>,
+        _seed_prefix: Vec<u8>,
+        _multisig: Pubkey,
+        _nonce_le_bytes: [u8; 8],
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    // Approvals the owner has cast while tracking was on, 0 when it is off
+    pub fn approval_count(ctx: Context<MultisigView>, _multisig_id: u64, owner: Pubkey) -> Result<u64> {
+        let multisig = &ctx.accounts.multisig;
+        let index = multisig.owner_index(&owner).ok_or(ErrorCode::NotOwner)?;
+
+        Ok(multisig.approval_counts.get(index).copied().unwrap_or(0))
+    }
+
+    // Read-only; the associated token account of `owner` for `mint`, derived the
+    // same way the program derives the multisig's own token accounts
+    pub fn resolve_ata(_ctx: Context<MultisigView>, _multisig_id: u64, mint: Pubkey, owner: Pubkey) -> Result<Pubkey> {
+        Ok(associated_token_address(&owner, &mint))
+    }
+
+    // Balance of every tracked mint. The multisig's associated token accounts
+    // must be passed as remaining accounts, in any order.
+    pub fn token_balances(ctx: Context<MultisigView>, _multisig_id: u64) -> Result<Vec<TokenBalance>> {
+        let multisig = &ctx.accounts.multisig;
+        let multisig_key = multisig.key();
+
+        multisig
+            .tracked_mints
+            .iter()
+            .map(|mint| {
+                let address = associated_token_address(&multisig_key, mint);
+                let info = ctx
+                    .remaining_accounts
+                    .iter()
+                    .find(|info| info.key == &address && info.owner == &TOKEN_PROGRAM_ID)
+                    .ok_or(ErrorCode::AccountMismatch)?;
+
+                // Token account layout: amount at 64..72
+                let data = info.try_borrow_data()?;
+                require!(data.len() >= 72, ErrorCode::InvalidTokenAccount);
+                let amount = u64::from_le_bytes(data[64..72].try_into().unwrap());
+
+                Ok(TokenBalance { mint: *mint, token_account: address, amount })
+            })
+            .collect()
+    }
+
+    // Nonces of proposals that are neither executed nor cancelled, oldest first,
+    // so clients can find pending transactions without a program account scan
+    pub fn list_open(ctx: Context<MultisigView>, _multisig_id: u64) -> Result<Vec<u64>> {
+        Ok(ctx.accounts.multisig.open_nonces.clone())
+    }
+
+    // No-op for integration smoke tests: checks the caller is an owner and emits Ping
+    pub fn ping(ctx: Context<PingMultisig>, _multisig_id: u64) -> Result<()> {
+        let caller = ctx.accounts.caller.key();
+        require!(ctx.accounts.multisig.owners.contains(&caller), ErrorCode::NotOwner);
+
+        emit!(Ping {
+            multisig: ctx.accounts.multisig.key(),
+            caller,
+            slot: Clock::get()?.slot,
+            event_seq: ctx.accounts.multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Creates the multisig's durable nonce account at its PDA, with the multisig
+    // as nonce authority, ready to pass to create_transaction. Anyone can pay
+    // for it since only the multisig can advance or withdraw from it.
+    pub fn create_nonce_account(ctx: Context<CreateNonceAccount>, _multisig_id: u64) -> Result<()> {
+        let multisig_key = ctx.accounts.multisig.key();
+        let nonce_account = ctx.accounts.nonce_account.to_account_info();
+        let seeds: &[&[u8]] = &[b"nonce", multisig_key.as_ref(), &[ctx.bumps.nonce_account]];
+
+        // Stays owned by the system program, which is what nonce accounts need
+        create_pda_account(
+            &nonce_account,
+            NonceState::size(),
+            &anchor_lang::system_program::ID,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            seeds,
+        )?;
+
+        let ix = anchor_lang::solana_program::instruction::Instruction::new_with_bincode(
+            anchor_lang::system_program::ID,
+            &SystemInstruction::InitializeNonceAccount(multisig_key),
+            vec![
+                AccountMeta::new(nonce_account.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.recent_blockhashes.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.rent.key(), false),
+            ],
+        );
+        invoke(
+            &ix,
+            &[
+                nonce_account.clone(),
+                ctx.accounts.recent_blockhashes.to_account_info(),
+                ctx.accounts.rent.to_account_info(),
+            ],
+        )?;
+
+        let multisig = &mut ctx.accounts.multisig;
+        emit!(NonceAccountCreated {
+            multisig: multisig_key,
+            nonce_account: nonce_account.key(),
+            payer: ctx.accounts.payer.key(),
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Lets a (typically cold) owner approve through a hot key. None clears it.
+    // The delegate only approves, it can't propose, reject or execute as the owner.
+    pub fn set_delegate(ctx: Context<SetDelegate>, _multisig_id: u64, delegate: Option<Pubkey>) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let multisig = &mut ctx.accounts.multisig;
+        require!(multisig.owners.contains(&owner), ErrorCode::NotOwner);
+
+        multisig.delegates.retain(|delegation| delegation.owner != owner);
+        if let Some(delegate) = delegate {
+            // Owners always act as themselves, and a hot key serves one owner only
+            require!(
+                !multisig.owners.contains(&delegate) && multisig.owner_for_delegate(&delegate).is_none(),
+                ErrorCode::DelegateInUse
+            );
+            require!(multisig.delegates.len() < MAX_DELEGATES, ErrorCode::TooManyDelegates);
+            multisig.delegates.push(Delegation { owner, delegate });
+        }
+
+        emit!(DelegateChanged {
+            multisig: multisig.key(),
+            owner,
+            delegate,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // In legacy_nonces mode cancelling frees the nonce: it is removed from
+    // used_nonces so the same nonce (and transaction PDA) can be used for a new
+    // proposal. Sequential nonces are never reused.
+    pub fn cancel_transaction(ctx: Context<CancelTransaction>, _multisig_id: u64, nonce: u64) -> Result<()> {
+        let canceller = ctx.accounts.canceller.key();
+        let multisig = &mut ctx.accounts.multisig;
+        let transaction = &ctx.accounts.transaction;
+
+        // Only the proposer or an owner can cancel
+        require!(
+            canceller == transaction.proposer || multisig.owners.contains(&canceller),
+            ErrorCode::NotOwner
+        );
+
+        require!(!transaction.did_execute, ErrorCode::AlreadyExecuted);
+        // The chunks already run can't be undone, so the rest has to run too
+        require!(transaction.executed_count == 0, ErrorCode::BatchInProgress);
+
+        // A proposal reusing a pinned nonce would satisfy the dependents in its place
+        if !multisig.pinned_nonces.contains(&nonce) {
+            multisig.used_nonces.retain(|used| *used != nonce);
+        }
+        multisig.open_tx_count = multisig.open_tx_count.saturating_sub(1);
+        multisig.open_nonces.retain(|open| *open != nonce);
+        unpin_dependency(multisig, transaction);
+
+        // The pause a hold set has nothing left to protect
+        if multisig.pending_large_tx.is_some_and(|hold| hold.nonce == nonce) {
+            multisig.pending_large_tx = None;
+            multisig.paused = false;
+        }
+        if multisig.released_large_tx.is_some_and(|release| release.nonce == nonce) {
+            multisig.released_large_tx = None;
+        }
+
+        emit!(TransactionCancelled {
+            multisig: multisig.key(),
+            transaction: transaction.key(),
+            cancelled_by: canceller,
+            nonce,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Frees the nonce of an executed transaction (closed or not) so the stored
+    // nonce set doesn't fill up. Cancelled transactions free theirs already.
+    // Only meaningful in legacy_nonces mode.
+    pub fn release_nonce(ctx: Context<ReleaseNonce>, _multisig_id: u64, nonce: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        require!(ctx.accounts.multisig.owners.contains(&owner), ErrorCode::NotOwner);
+
+        let multisig = &mut ctx.accounts.multisig;
+        require!(multisig.used_nonces.contains(&nonce), ErrorCode::NonceNotFound);
+
+        // An empty account means the transaction was executed and then closed
+        let transaction_info = &ctx.accounts.transaction;
+        if !transaction_info.data_is_empty() {
+            let transaction = Transaction::try_deserialize(&mut &transaction_info.try_borrow_data()?[..])?;
+            require!(transaction.did_execute, ErrorCode::NotExecuted);
+        }
+
+        multisig.used_nonces.retain(|used| *used != nonce);
+
+        emit!(NonceReleased {
+            multisig: multisig.key(),
+            nonce,
+            released_by: owner,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Lets any single owner move SOL out of the vault without a proposal as long
+    // as the running total for the current UTC day stays within daily_limit.
+    // Larger transfers must go through propose_sol_transfer.
+    pub fn fast_transfer(ctx: Context<FastTransfer>, _multisig_id: u64, recipient: Pubkey, amount: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let multisig = &mut ctx.accounts.multisig;
+
+        require!(multisig.owners.contains(&owner), ErrorCode::NotOwner);
+        require!(!multisig.paused, ErrorCode::MultisigPaused);
+        require_keys_eq!(ctx.accounts.recipient.key(), recipient, ErrorCode::AccountMismatch);
+        require_keys_neq!(recipient, ctx.accounts.vault.key(), ErrorCode::SelfTransferNotAllowed);
+        require!(multisig.recipient_allowed(&[recipient]), ErrorCode::RecipientNotAllowed);
+
+        // Reset the counter when a new UTC day has started
+        let now = Clock::get()?.unix_timestamp;
+        if now / SECONDS_PER_DAY != multisig.last_reset_ts / SECONDS_PER_DAY {
+            multisig.spent_today = 0;
+            multisig.last_reset_ts = now;
+        }
+
+        let spent_today = multisig
+            .spent_today
+            .checked_add(amount)
+            .ok_or(ErrorCode::DailyLimitExceeded)?;
+        require!(spent_today <= multisig.daily_limit, ErrorCode::DailyLimitExceeded);
+        multisig.spent_today = spent_today;
+
+        let multisig_key = multisig.key();
+        let vault_seeds: &[&[u8]] = &[b"vault", multisig_key.as_ref(), &[ctx.bumps.vault]];
+
+        invoke_signed(
+            &system_instruction::transfer(&ctx.accounts.vault.key(), &recipient, amount),
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.recipient.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[vault_seeds],
+        )?;
+
+        emit!(FastTransferExecuted {
+            multisig: multisig_key,
+            owner,
+            recipient,
+            amount,
+            spent_today,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Plain transfers into the vault work too; this just leaves an event behind
+    // for indexers, optionally tagged with a memo
+    pub fn deposit(ctx: Context<Deposit>, _multisig_id: u64, amount: u64, memo: [u8; 32]) -> Result<()> {
+        let depositor = ctx.accounts.depositor.key();
+        let vault = ctx.accounts.vault.key();
+
+        invoke(
+            &system_instruction::transfer(&depositor, &vault, amount),
+            &[
+                ctx.accounts.depositor.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        emit!(Deposited {
+            multisig: ctx.accounts.multisig.key(),
+            depositor,
+            amount,
+            new_balance: ctx.accounts.vault.lamports(),
+            memo,
+            event_seq: ctx.accounts.multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Creates the multisig's associated token account for `mint` (if it doesn't
+    // exist yet) and adds the mint to tracked_mints. Owners only, `owner` pays rent.
+    pub fn register_token(ctx: Context<RegisterToken>, _multisig_id: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let multisig = &mut ctx.accounts.multisig;
+        let mint = ctx.accounts.mint.key();
+
+        require!(multisig.owners.contains(&owner), ErrorCode::NotOwner);
+        require!(!multisig.tracked_mints.contains(&mint), ErrorCode::TokenAlreadyRegistered);
+        require!(
+            multisig.tracked_mints.len() < MAX_TRACKED_MINTS,
+            ErrorCode::TooManyTrackedMints
+        );
+
+        let multisig_key = multisig.key();
+        let token_account = ctx.accounts.token_account.key();
+        require_keys_eq!(
+            token_account,
+            associated_token_address(&multisig_key, &mint),
+            ErrorCode::InvalidTokenAccount
+        );
+
+        // CreateIdempotent, so an account someone already created is adopted as is
+        let ix = anchor_lang::solana_program::instruction::Instruction {
+            program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(owner, true),
+                AccountMeta::new(token_account, false),
+                AccountMeta::new_readonly(multisig_key, false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ],
+            data: vec![1],
+        };
+        invoke(
+            &ix,
+            &[
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.token_account.to_account_info(),
+                multisig.to_account_info(),
+                ctx.accounts.mint.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+        )?;
+
+        multisig.tracked_mints.push(mint);
+
+        emit!(TokenRegistered {
+            multisig: multisig_key,
+            mint,
+            token_account,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Escape hatch outside the proposal flow: every owner signs this one
+    // instruction (as signer remaining accounts) and the whole vault is swept to
+    // `recovery`. Works while paused. Transaction size limits how many owners
+    // can realistically sign at once.
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>, _multisig_id: u64, recovery: Pubkey) -> Result<()> {
+        require_keys_eq!(ctx.accounts.recovery.key(), recovery, ErrorCode::AccountMismatch);
+
+        let multisig = &ctx.accounts.multisig;
+        for owner in &multisig.owners {
+            require!(
+                ctx.remaining_accounts
+                    .iter()
+                    .any(|info| info.key == owner && info.is_signer),
+                ErrorCode::MissingOwnerSignature
+            );
+        }
+
+        let multisig_key = multisig.key();
+        let vault_seeds: &[&[u8]] = &[b"vault", multisig_key.as_ref(), &[ctx.bumps.vault]];
+        let amount = ctx.accounts.vault.lamports();
+
+        invoke_signed(
+            &system_instruction::transfer(&ctx.accounts.vault.key(), &recovery, amount),
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.recovery.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[vault_seeds],
+        )?;
+
+        emit!(EmergencyWithdrawn {
+            multisig: multisig_key,
+            recovery,
+            amount,
+            event_seq: ctx.accounts.multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Reclaims the rent of an executed transaction. Anyone can call it since the
+    // lamports always go back to the proposer. The nonce stays in used_nonces.
+    pub fn close_transaction(ctx: Context<CloseTransaction>, _multisig_id: u64, _nonce: u64) -> Result<()> {
+        emit!(TransactionClosed {
+            transaction: ctx.accounts.transaction.key(),
+            proposer: ctx.accounts.proposer.key(),
+            event_seq: ctx.accounts.multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Emergency brake for the creator. While paused no proposals can be created
+    // or executed, but approvals and cancellations still work.
+    pub fn set_paused(ctx: Context<SetPaused>, _multisig_id: u64, paused: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.paused = paused;
+
+        emit!(PauseStateChanged {
+            multisig: multisig.key(),
+            paused,
+            authority: ctx.accounts.authority.key(),
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Creator-only, like set_paused. Self-administration through proposals is
+    // only possible while this is on, so it can't be re-enabled by a proposal.
+    pub fn set_allow_self_calls(ctx: Context<CreatorAuth>, _multisig_id: u64, allowed: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.allow_self_calls = allowed;
+
+        emit!(SelfCallsToggled {
+            multisig: multisig.key(),
+            allowed,
+            authority: ctx.accounts.authority.key(),
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Hands the creator role (pause, self-call toggle) to another owner
+    pub fn transfer_creator(ctx: Context<CreatorAuth>, _multisig_id: u64, new_creator: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        require!(multisig.owners.contains(&new_creator), ErrorCode::NotOwner);
+
+        let old_creator = multisig.creator;
+        multisig.creator = new_creator;
+
+        emit!(CreatorTransferred {
+            multisig: multisig.key(),
+            old_creator,
+            new_creator,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Lets execution run up to `expiry_grace` seconds past expires_at, so relayer
+    // latency can't strand a proposal approved just before its deadline.
+    // Approvals still stop at expires_at.
+    pub fn set_expiry_grace(ctx: Context<MultisigAuth>, _multisig_id: u64, expiry_grace: i64) -> Result<()> {
+        require!(expiry_grace >= 0, ErrorCode::InvalidExpiryGrace);
+
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.expiry_grace = expiry_grace;
+
+        emit!(ExpiryGraceChanged {
+            multisig: multisig.key(),
+            expiry_grace,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // 0 disables the spacing check.
+    pub fn set_min_vote_spacing(ctx: Context<MultisigAuth>, _multisig_id: u64, min_vote_spacing: i64) -> Result<()> {
+        require!(min_vote_spacing >= 0, ErrorCode::InvalidVoteSpacing);
+
+        let multisig = &mut ctx.accounts.multisig;
+        let old_spacing = multisig.min_vote_spacing;
+        multisig.min_vote_spacing = min_vote_spacing;
+
+        emit!(MinVoteSpacingChanged {
+            multisig: multisig.key(),
+            old_spacing,
+            new_spacing: min_vote_spacing,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // max_tx_per_window = 0 disables the auto-pause.
+    pub fn set_rate_limit(
+        ctx: Context<MultisigAuth>,
+        _multisig_id: u64,
+        max_tx_per_window: u16,
+        window_secs: i64,
+    ) -> Result<()> {
+        require!(
+            max_tx_per_window as usize <= MAX_RATE_LIMIT
+                && (max_tx_per_window == 0 || window_secs > 0),
+            ErrorCode::InvalidRateLimit
+        );
+
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.max_tx_per_window = max_tx_per_window;
+        multisig.window_secs = window_secs;
+        multisig.recent_creations.clear();
+
+        emit!(RateLimitChanged {
+            multisig: multisig.key(),
+            max_tx_per_window,
+            window_secs,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // A limit of 0 disables fast_transfer. Delayed like membership changes, so
+    // a quorum can't raise the limit and drain through fast_transfer at once.
+    pub fn set_daily_limit(ctx: Context<MultisigAuth>, _multisig_id: u64, daily_limit: u64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.min_governance_delay > 0 {
+            return queue_governance_change(multisig, GovernanceAction::SetDailyLimit { daily_limit });
+        }
+
+        apply_daily_limit(multisig, daily_limit)
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA
+    pub fn rename(ctx: Context<MultisigAuth>, _multisig_id: u64, new_name: [u8; 32]) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+
+        let old_name = multisig.name;
+        multisig.name = new_name;
+
+        emit!(MultisigRenamed {
+            multisig: multisig.key(),
+            old_name,
+            new_name,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA
+    pub fn change_threshold(ctx: Context<MultisigAuth>, _multisig_id: u64, new_threshold: u8) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.min_governance_delay > 0 {
+            return queue_governance_change(multisig, GovernanceAction::ChangeThreshold { new_threshold });
+        }
+
+        apply_change_threshold(multisig, new_threshold)
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Like change_threshold for weighted multisigs whose total weight outgrows a u8.
+    pub fn set_weight_threshold(ctx: Context<MultisigAuth>, _multisig_id: u64, weight_threshold: u64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.min_governance_delay > 0 {
+            return queue_governance_change(multisig, GovernanceAction::SetWeightThreshold { weight_threshold });
+        }
+
+        apply_weight_threshold(multisig, weight_threshold)
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Switches to percentage mode: the required weight becomes
+    // ceil(total_weight * threshold_bps / 10000) and follows membership changes.
+    pub fn set_threshold_bps(ctx: Context<MultisigAuth>, _multisig_id: u64, threshold_bps: u16) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.min_governance_delay > 0 {
+            return queue_governance_change(multisig, GovernanceAction::SetThresholdBps { threshold_bps });
+        }
+
+        apply_threshold_bps(multisig, threshold_bps)
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // The account grows by one owner slot when it is full, with rent paid by `payer`
+    // (typically the vault, which execute_transaction also signs for).
+    pub fn add_owner(ctx: Context<AddOwner>, _multisig_id: u64, new_owner: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.min_governance_delay > 0 {
+            return queue_governance_change(multisig, GovernanceAction::AddOwner { new_owner });
+        }
+
+        apply_add_owner(multisig, &ctx.accounts.payer, &ctx.accounts.system_program, new_owner)
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // First step of adding an owner safely: the candidate only joins once they
+    // sign accept_ownership, which proves the key is live. A later nomination
+    // replaces a pending one.
+    pub fn nominate_owner(ctx: Context<MultisigAuth>, _multisig_id: u64, candidate: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.min_governance_delay > 0 {
+            return queue_governance_change(multisig, GovernanceAction::NominateOwner { candidate });
+        }
+
+        apply_nominate_owner(multisig, candidate)
+    }
+
+    // Called by the nominee within NOMINATION_WINDOW of the nomination. The
+    // nominee pays for the extra owner slot if the account has to grow.
+    pub fn accept_ownership(ctx: Context<AcceptOwnership>, _multisig_id: u64) -> Result<()> {
+        let candidate = ctx.accounts.candidate.key();
+        let multisig = &mut ctx.accounts.multisig;
+        require!(!multisig.paused, ErrorCode::MultisigPaused);
+
+        let nomination = multisig
+            .pending_nomination
+            .filter(|nomination| nomination.candidate == candidate)
+            .ok_or(ErrorCode::NoPendingNomination)?;
+        require!(
+            Clock::get()?.unix_timestamp <= nomination.expires_at,
+            ErrorCode::NominationExpired
+        );
+        multisig.pending_nomination = None;
+
+        apply_add_owner(multisig, &ctx.accounts.candidate, &ctx.accounts.system_program, candidate)
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Swaps in a whole new owner set and threshold in one step, growing the account
+    // (paid by `payer`) if needed. Weights are reset to one vote each; owners who
+    // stay keep their role and delegate. Too large a change to fit in a queued
+    // governance action, so it is refused while a governance delay is set.
+    pub fn set_owners(
+        ctx: Context<AddOwner>,
+        _multisig_id: u64,
+        new_owners: Vec<Pubkey>,
+        new_threshold: u8,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        require!(multisig.min_governance_delay == 0, ErrorCode::GovernanceDelayActive);
+
+        require!(!new_owners.is_empty(), ErrorCode::NoOwners);
+        require!(new_owners.len() <= MAX_OWNERS, ErrorCode::TooManyOwners);
+        if new_threshold == 0 || new_threshold as usize > new_owners.len() {
+            msg!("threshold {} must be between 1 and owner count {}", new_threshold, new_owners.len());
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+        require!(new_owners.len() >= multisig.min_owners as usize, ErrorCode::TooFewOwners);
+
+        let mut unique = new_owners.clone();
+        unique.sort();
+        unique.dedup();
+        require!(unique.len() == new_owners.len(), ErrorCode::DuplicateOwners);
+
+        if !multisig.roles.is_empty() {
+            let old_roles: Vec<(Pubkey, [u8; 16])> =
+                multisig.owners.iter().copied().zip(multisig.roles.iter().copied()).collect();
+            multisig.roles = new_owners
+                .iter()
+                .map(|owner| {
+                    old_roles
+                        .iter()
+                        .find(|(old, _)| old == owner)
+                        .map_or([0; 16], |(_, role)| *role)
+                })
+                .collect();
+        }
+        if !multisig.approval_counts.is_empty() {
+            let old_counts: Vec<(Pubkey, u64)> =
+                multisig.owners.iter().copied().zip(multisig.approval_counts.iter().copied()).collect();
+            multisig.approval_counts = new_owners
+                .iter()
+                .map(|owner| old_counts.iter().find(|(old, _)| old == owner).map_or(0, |(_, count)| *count))
+                .collect();
+        }
+        multisig.delegates.retain(|delegation| {
+            new_owners.contains(&delegation.owner) && !new_owners.contains(&delegation.delegate)
+        });
+
+        multisig.veto_owners.retain(|veto_owner| new_owners.contains(veto_owner));
+        multisig.pending_nomination = None;
+
+        let old_owners = std::mem::replace(&mut multisig.owners, new_owners);
+        multisig.owner_set_seq += 1;
+        let old_threshold = multisig.threshold;
+        multisig.threshold = new_threshold;
+        multisig.threshold_mode = ThresholdMode::Absolute;
+        multisig.weights = Vec::new();
+        multisig.weight_threshold = 0;
+        require!(multisig.role_quorum_reachable(), ErrorCode::RoleQuorumUnreachable);
+
+        fit_owners(multisig, &ctx.accounts.payer, &ctx.accounts.system_program)?;
+
+        emit!(OwnersReplaced {
+            multisig: multisig.key(),
+            old_owners,
+            new_owners: multisig.owners.clone(),
+            old_threshold,
+            new_threshold,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // The new key takes over the old key's slot (and weight). This bumps
+    // owner_set_seq, so approvals on pending transactions stop counting and the
+    // owners have to approve those transactions again.
+    pub fn replace_owner(
+        ctx: Context<MultisigAuth>,
+        _multisig_id: u64,
+        old_owner: Pubkey,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.min_governance_delay > 0 {
+            return queue_governance_change(multisig, GovernanceAction::ReplaceOwner { old_owner, new_owner });
+        }
+
+        apply_replace_owner(multisig, old_owner, new_owner)
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Pass new_threshold to lower the threshold atomically when removal would
+    // otherwise leave it unreachable.
+    pub fn remove_owner(
+        ctx: Context<MultisigAuth>,
+        _multisig_id: u64,
+        owner_to_remove: Pubkey,
+        new_threshold: Option<u8>,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.min_governance_delay > 0 {
+            return queue_governance_change(
+                multisig,
+                GovernanceAction::RemoveOwner { owner: owner_to_remove, new_threshold },
+            );
+        }
+
+        apply_remove_owner(multisig, owner_to_remove, new_threshold)
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // While a delay is in force, changing it is itself a delayed governance change,
+    // so a hostile quorum can't switch it off and act instantly.
+    pub fn set_governance_delay(ctx: Context<MultisigAuth>, _multisig_id: u64, min_governance_delay: i64) -> Result<()> {
+        require!(min_governance_delay >= 0, ErrorCode::InvalidGovernanceDelay);
+
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.min_governance_delay > 0 {
+            return queue_governance_change(multisig, GovernanceAction::SetGovernanceDelay { min_governance_delay });
+        }
+
+        apply_governance_delay(multisig, min_governance_delay)
+    }
+
+    // Applies the queued governance change once its eta has passed. Anyone can
+    // call it; `payer` covers the realloc if an added owner needs a new slot.
+    pub fn apply_governance_change(ctx: Context<ApplyGovernanceChange>, _multisig_id: u64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let change = multisig
+            .pending_governance_change
+            .take()
+            .ok_or(ErrorCode::NoPendingGovernanceChange)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= change.eta, ErrorCode::GovernanceChangeNotReady);
+
+        emit!(GovernanceChangeApplied {
+            multisig: multisig.key(),
+            action: change.action,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        match change.action {
+            GovernanceAction::AddOwner { new_owner } => apply_add_owner(
+                multisig,
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+                new_owner,
+            ),
+            GovernanceAction::RemoveOwner { owner, new_threshold } => {
+                apply_remove_owner(multisig, owner, new_threshold)
+            }
+            GovernanceAction::ReplaceOwner { old_owner, new_owner } => {
+                apply_replace_owner(multisig, old_owner, new_owner)
+            }
+            GovernanceAction::ChangeThreshold { new_threshold } => apply_change_threshold(multisig, new_threshold),
+            GovernanceAction::SetThresholdBps { threshold_bps } => apply_threshold_bps(multisig, threshold_bps),
+            GovernanceAction::SetGovernanceDelay { min_governance_delay } => {
+                apply_governance_delay(multisig, min_governance_delay)
+            }
+            GovernanceAction::SetDailyLimit { daily_limit } => apply_daily_limit(multisig, daily_limit),
+            GovernanceAction::SetMaxTxValue { max_tx_value } => apply_max_tx_value(multisig, max_tx_value),
+            GovernanceAction::NominateOwner { candidate } => apply_nominate_owner(multisig, candidate),
+            GovernanceAction::SetWeightThreshold { weight_threshold } => {
+                apply_weight_threshold(multisig, weight_threshold)
+            }
+        }
+    }
+
+    // Any single owner can drop a queued governance change during its delay
+    pub fn veto_governance_change(ctx: Context<VetoGovernanceChange>, _multisig_id: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let multisig = &mut ctx.accounts.multisig;
+
+        require!(multisig.owners.contains(&owner), ErrorCode::NotOwner);
+        let change = multisig
+            .pending_governance_change
+            .take()
+            .ok_or(ErrorCode::NoPendingGovernanceChange)?;
+
+        emit!(GovernanceChangeVetoed {
+            multisig: multisig.key(),
+            action: change.action,
+            vetoed_by: owner,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // When set, only owners can execute; relayers are turned away.
+    pub fn set_executor_must_be_owner(ctx: Context<MultisigAuth>, _multisig_id: u64, required: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.executor_must_be_owner = required;
+
+        emit!(ExecutorPolicyChanged {
+            multisig: multisig.key(),
+            executor_must_be_owner: required,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // When set, revoking below the threshold leaves a started timelock running.
+    pub fn set_persist_timelock_on_revoke(ctx: Context<MultisigAuth>, _multisig_id: u64, persist: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.persist_timelock_on_revoke = persist;
+
+        emit!(RevokePolicyChanged {
+            multisig: multisig.key(),
+            persist_timelock_on_revoke: persist,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Roles are UTF-8 labels, zero padded; an all-zero role means none.
+    pub fn set_owner_role(ctx: Context<MultisigAuth>, _multisig_id: u64, owner: Pubkey, role: [u8; 16]) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let index = multisig
+            .owners
+            .iter()
+            .position(|o| *o == owner)
+            .ok_or(ErrorCode::NotOwner)?;
+
+        // Roles start out empty and become parallel to owners on first use
+        if multisig.roles.is_empty() {
+            multisig.roles = vec![[0; 16]; multisig.owners.len()];
+        }
+        multisig.roles[index] = role;
+        require!(multisig.role_quorum_reachable(), ErrorCode::RoleQuorumUnreachable);
+
+        emit!(OwnerRoleChanged {
+            multisig: multisig.key(),
+            owner,
+            role,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Enabling starts every owner's approval count at zero, disabling drops the counts.
+    pub fn set_approval_tracking(ctx: Context<MultisigAuth>, _multisig_id: u64, enabled: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.approval_counts = if enabled { vec![0; multisig.owners.len()] } else { Vec::new() };
+
+        emit!(ApprovalTrackingChanged {
+            multisig: multisig.key(),
+            enabled,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // On top of the threshold, every execution needs at least `count` approvers
+    // holding each listed role. An empty list removes the requirement.
+    pub fn set_required_roles(
+        ctx: Context<MultisigAuth>,
+        _multisig_id: u64,
+        required_roles: Vec<RoleRequirement>,
+    ) -> Result<()> {
+        require!(required_roles.len() <= MAX_REQUIRED_ROLES, ErrorCode::InvalidRoleRequirement);
+        for (i, requirement) in required_roles.iter().enumerate() {
+            require!(
+                requirement.count > 0
+                    && requirement.role != [0; 16]
+                    && required_roles[..i].iter().all(|other| other.role != requirement.role),
+                ErrorCode::InvalidRoleRequirement
+            );
+        }
+
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.required_roles = required_roles;
+        require!(multisig.role_quorum_reachable(), ErrorCode::RoleQuorumUnreachable);
+
+        emit!(RequiredRolesChanged {
+            multisig: multisig.key(),
+            required_roles: multisig.required_roles.clone(),
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Lets proposals that can't write to any account (reads, log CPIs) execute
+    // with fewer approvals. 0 restores the regular threshold for them.
+    pub fn set_readonly_threshold(ctx: Context<MultisigAuth>, _multisig_id: u64, readonly_threshold: u8) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if readonly_threshold as u64 > multisig.total_weight() {
+            msg!("readonly threshold {} exceeds total weight {}", readonly_threshold, multisig.total_weight());
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+        multisig.readonly_threshold = readonly_threshold;
+
+        emit!(ReadonlyThresholdChanged {
+            multisig: multisig.key(),
+            readonly_threshold,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Replaces the veto owner list, which must be a subset of the owners.
+    pub fn set_veto_owners(ctx: Context<MultisigAuth>, _multisig_id: u64, veto_owners: Vec<Pubkey>) -> Result<()> {
+        require!(veto_owners.len() <= MAX_VETO_OWNERS, ErrorCode::TooManyOwners);
+
+        let multisig = &mut ctx.accounts.multisig;
+        require!(
+            veto_owners.iter().all(|veto_owner| multisig.owners.contains(veto_owner)),
+            ErrorCode::NotOwner
+        );
+
+        let mut unique = veto_owners.clone();
+        unique.sort();
+        unique.dedup();
+        require!(unique.len() == veto_owners.len(), ErrorCode::DuplicateOwners);
+
+        multisig.veto_owners = veto_owners;
+
+        emit!(VetoOwnersChanged {
+            multisig: multisig.key(),
+            veto_owners: multisig.veto_owners.clone(),
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Per-proposal cap on SOL moved by system transfers, checked at execution.
+    pub fn set_max_tx_value(ctx: Context<MultisigAuth>, _multisig_id: u64, max_tx_value: u64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.min_governance_delay > 0 {
+            return queue_governance_change(multisig, GovernanceAction::SetMaxTxValue { max_tx_value });
+        }
+
+        apply_max_tx_value(multisig, max_tx_value)
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // When set, executed proposals keep their instructions as an audit record.
+    pub fn set_retain_executed_data(ctx: Context<MultisigAuth>, _multisig_id: u64, retain: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.retain_executed_data = retain;
+
+        emit!(RetentionPolicyChanged {
+            multisig: multisig.key(),
+            retain_executed_data: retain,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // When set, an account may appear only once per proposed instruction.
+    pub fn set_reject_duplicate_accounts(ctx: Context<MultisigAuth>, _multisig_id: u64, reject: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.reject_duplicate_accounts = reject;
+
+        emit!(DuplicateAccountPolicyChanged {
+            multisig: multisig.key(),
+            reject_duplicates: reject,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Once the list is non-empty proposals may only target listed programs.
+    pub fn add_allowed_program(ctx: Context<MultisigAuth>, _multisig_id: u64, program_id: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+
+        require!(
+            !multisig.allowed_programs.contains(&program_id),
+            ErrorCode::AlreadyAllowed
+        );
+        require!(
+            multisig.allowed_programs.len() < MAX_ALLOWED_PROGRAMS,
+            ErrorCode::AllowlistFull
+        );
+
+        multisig.allowed_programs.push(program_id);
+
+        emit!(AllowedProgramsChanged {
+            multisig: multisig.key(),
+            program_id,
+            allowed: true,
+            count: multisig.allowed_programs.len() as u8,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Removing the last entry lifts the restriction entirely.
+    pub fn remove_allowed_program(ctx: Context<MultisigAuth>, _multisig_id: u64, program_id: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+
+        let index = multisig
+            .allowed_programs
+            .iter()
+            .position(|allowed| *allowed == program_id)
+            .ok_or(ErrorCode::ProgramNotAllowed)?;
+        multisig.allowed_programs.remove(index);
+
+        emit!(AllowedProgramsChanged {
+            multisig: multisig.key(),
+            program_id,
+            allowed: false,
+            count: multisig.allowed_programs.len() as u8,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Once the list is non-empty the transfer helpers only pay listed recipients.
+    pub fn add_allowed_recipient(ctx: Context<MultisigAuth>, _multisig_id: u64, recipient: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+
+        require!(
+            !multisig.allowed_recipients.contains(&recipient),
+            ErrorCode::AlreadyAllowed
+        );
+        require!(
+            multisig.allowed_recipients.len() < MAX_ALLOWED_RECIPIENTS,
+            ErrorCode::AllowlistFull
+        );
+
+        multisig.allowed_recipients.push(recipient);
+
+        emit!(AllowedRecipientsChanged {
+            multisig: multisig.key(),
+            recipient,
+            allowed: true,
+            count: multisig.allowed_recipients.len() as u8,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Removing the last entry lifts the restriction entirely.
+    pub fn remove_allowed_recipient(ctx: Context<MultisigAuth>, _multisig_id: u64, recipient: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+
+        let index = multisig
+            .allowed_recipients
+            .iter()
+            .position(|allowed| *allowed == recipient)
+            .ok_or(ErrorCode::RecipientNotAllowed)?;
+        multisig.allowed_recipients.remove(index);
+
+        emit!(AllowedRecipientsChanged {
+            multisig: multisig.key(),
+            recipient,
+            allowed: false,
+            count: multisig.allowed_recipients.len() as u8,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA,
+    // as the proposal's last instruction. Closes the multisig and refunds its rent
+    // to the creator once the vault is empty and the proposal running this is the
+    // only open one.
+    pub fn close_multisig(ctx: Context<CloseMultisig>, _multisig_id: u64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+
+        let executing_nonce = multisig.executing_nonce;
+        require!(
+            multisig.executing
+                && multisig.open_tx_count <= 1
+                && multisig.open_nonces.iter().all(|open| *open == executing_nonce)
+                && ctx.accounts.vault.lamports() == 0,
+            ErrorCode::MultisigNotEmpty
+        );
+
+        emit!(MultisigClosed {
+            multisig: multisig.key(),
+            creator: multisig.creator,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Upgrades a multisig written with an older (shorter) layout. Missing
+    // trailing fields are read as zeros, which are the defaults for every
+    // appended field unless a fixup below says otherwise; the account is then
+    // grown to the current size, paid by `payer`, and rewritten. Anyone can call it.
+    pub fn migrate(ctx: Context<Migrate>, _multisig_id: u64) -> Result<()> {
+        let info = ctx.accounts.multisig.to_account_info();
+
+        let mut multisig = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == *Multisig::DISCRIMINATOR,
+                ErrorCode::AccountMismatch
+            );
+
+            let mut padded = data.to_vec();
+            padded.resize(data.len().max(Multisig::space(MAX_OWNERS)), 0);
+            Multisig::try_deserialize(&mut &padded[..])?
+        };
+
+        let from_version = multisig.version;
+        require!(from_version < MULTISIG_VERSION, ErrorCode::AlreadyMigrated);
+
+        // Per-version fixups for fields whose safe default isn't zero go here
+        if from_version < 6 {
+            // Older multisigs always allocated the full data size
+            multisig.max_data_size = MAX_INSTRUCTION_DATA_SIZE as u16;
+        }
+        if from_version < 8 {
+            multisig.min_owners = 1;
+        }
+        if from_version < 11 {
+            multisig.bump = ctx.bumps.multisig;
+        }
+        multisig.version = MULTISIG_VERSION;
+        let event_seq = multisig.next_event_seq();
+
+        let owner_capacity = (multisig.owners.len() + OWNER_HEADROOM).min(MAX_OWNERS);
+        grow_account(
+            &info,
+            Multisig::space(owner_capacity),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        multisig.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(MultisigMigrated {
+            multisig: info.key(),
+            from_version,
+            to_version: MULTISIG_VERSION,
+            event_seq,
+        });
+
+        Ok(())
+    }
+
+    // Emergency brake held by the guardians, independent of the owner threshold:
+    // once guardian_threshold guardians vote for the same state (paused or not,
+    // the latter overriding an auto-pause) it is applied and the votes reset.
+    pub fn guardian_set_paused(ctx: Context<GuardianPause>, _multisig_id: u64, paused: bool) -> Result<()> {
+        let guardian = ctx.accounts.guardian.key();
+        let multisig = &mut ctx.accounts.multisig;
+        require!(multisig.guardians.contains(&guardian), ErrorCode::NotGuardian);
+
+        // A guardian's latest vote replaces their earlier one
+        multisig.pause_votes.retain(|vote| vote.guardian != guardian);
+        multisig.pause_votes.push(PauseVote { guardian, paused });
+
+        let votes = multisig.pause_votes.iter().filter(|vote| vote.paused == paused).count();
+        emit!(GuardianPauseVoted {
+            multisig: multisig.key(),
+            guardian,
+            paused,
+            votes: votes as u8,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        if votes >= multisig.guardian_threshold as usize {
+            multisig.paused = paused;
+            multisig.pause_votes.clear();
+
+            emit!(PauseStateChanged {
+                multisig: multisig.key(),
+                paused,
+                authority: guardian,
+                event_seq: multisig.next_event_seq(),
+            });
+        }
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // Executions moving more than `large_outflow_bps` of the vault balance are
+    // held until a guardian confirms them. 0 turns the circuit breaker off.
+    pub fn set_large_outflow_threshold(ctx: Context<MultisigAuth>, _multisig_id: u64, large_outflow_bps: u16) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        require!(large_outflow_bps <= BPS_DENOMINATOR, ErrorCode::InvalidThresholdBps);
+        // Nobody could release a hold otherwise
+        require!(large_outflow_bps == 0 || !multisig.guardians.is_empty(), ErrorCode::NoGuardians);
+        multisig.large_outflow_bps = large_outflow_bps;
+
+        emit!(LargeOutflowThresholdChanged {
+            multisig: multisig.key(),
+            large_outflow_bps,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Any single guardian releases a held large outflow within LARGE_OUTFLOW_WINDOW
+    // of the hold. This lifts the pause the hold set; the next execution then runs it.
+    pub fn confirm_large_outflow(ctx: Context<ConfirmLargeOutflow>, _multisig_id: u64, _nonce: u64) -> Result<()> {
+        let guardian = ctx.accounts.guardian.key();
+        let multisig = &mut ctx.accounts.multisig;
+        let transaction = &ctx.accounts.transaction;
+        require!(multisig.guardians.contains(&guardian), ErrorCode::NotGuardian);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            multisig
+                .pending_large_tx
+                .is_some_and(|hold| hold.nonce == transaction.nonce && now <= hold.held_at + LARGE_OUTFLOW_WINDOW),
+            ErrorCode::LargeOutflowNotHeld
+        );
+        multisig.pending_large_tx = None;
+        multisig.released_large_tx = Some(LargeOutflowRelease {
+            nonce: transaction.nonce,
+            tx_hash: transaction.tx_hash,
+        });
+        multisig.paused = false;
+
+        emit!(LargeOutflowConfirmed {
+            multisig: multisig.key(),
+            transaction: transaction.key(),
+            guardian,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Only callable through execute_transaction, which signs for the multisig PDA.
+    // An empty guardian set with a zero threshold disables recovery.
+    pub fn set_guardians(
+        ctx: Context<MultisigAuth>,
+        _multisig_id: u64,
+        guardians: Vec<Pubkey>,
+        guardian_threshold: u8,
+    ) -> Result<()> {
+        require!(guardians.len() <= MAX_GUARDIANS, ErrorCode::TooManyGuardians);
+        if guardian_threshold as usize > guardians.len() || (guardian_threshold == 0 && !guardians.is_empty()) {
+            msg!("guardian threshold {} must be between 1 and guardian count {}", guardian_threshold, guardians.len());
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+
+        let mut unique = guardians.clone();
+        unique.sort();
+        unique.dedup();
+        require!(unique.len() == guardians.len(), ErrorCode::DuplicateOwners);
+
+        let multisig = &mut ctx.accounts.multisig;
+        // Held large outflows would have nobody to release them
+        require!(!guardians.is_empty() || multisig.large_outflow_bps == 0, ErrorCode::NoGuardians);
+        multisig.guardians = guardians;
+        multisig.guardian_threshold = guardian_threshold;
+        multisig.pause_votes.clear();
+
+        emit!(GuardiansChanged {
+            multisig: multisig.key(),
+            guardians: multisig.guardians.clone(),
+            guardian_threshold,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Opens a recovery that replaces the whole owner set, counting as the
+    // calling guardian's approval. Only one can be pending per multisig.
+    pub fn recover(
+        ctx: Context<Recover>,
+        _multisig_id: u64,
+        new_owners: Vec<Pubkey>,
+        new_threshold: u8,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let guardian = ctx.accounts.guardian.key();
+
+        require!(multisig.guardians.contains(&guardian), ErrorCode::NotGuardian);
+
+        require!(!new_owners.is_empty(), ErrorCode::NoOwners);
+        require!(new_owners.len() <= MAX_OWNERS, ErrorCode::TooManyOwners);
+        if new_threshold == 0 || new_threshold as usize > new_owners.len() {
+            msg!("threshold {} must be between 1 and owner count {}", new_threshold, new_owners.len());
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+
+        let mut unique = new_owners.clone();
+        unique.sort();
+        unique.dedup();
+        require!(unique.len() == new_owners.len(), ErrorCode::DuplicateOwners);
+        require!(new_owners.len() >= multisig.min_owners as usize, ErrorCode::TooFewOwners);
+
+        let recovery = &mut ctx.accounts.recovery;
+        recovery.multisig = multisig.key();
+        recovery.proposer = guardian;
+        recovery.new_owners = new_owners;
+        recovery.new_threshold = new_threshold;
+        recovery.approvals = Vec::new();
+        recovery.eta = 0;
+
+        emit!(RecoveryProposed {
+            multisig: multisig.key(),
+            recovery: recovery.key(),
+            guardian,
+            new_owners: recovery.new_owners.clone(),
+            new_threshold,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        record_recovery_approval(multisig, recovery, guardian)
+    }
+
+    pub fn approve_recovery(ctx: Context<ApproveRecovery>, _multisig_id: u64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let guardian = ctx.accounts.guardian.key();
+
+        require!(multisig.guardians.contains(&guardian), ErrorCode::NotGuardian);
+        require!(
+            !ctx.accounts.recovery.approvals.contains(&guardian),
+            ErrorCode::AlreadyApproved
+        );
+
+        record_recovery_approval(multisig, &mut ctx.accounts.recovery, guardian)
+    }
+
+    // Anyone can finalize once the guardian threshold was reached and
+    // RECOVERY_DELAY has passed; the payer covers any realloc.
+    pub fn finalize_recovery(ctx: Context<FinalizeRecovery>, _multisig_id: u64) -> Result<()> {
+        let recovery = &ctx.accounts.recovery;
+        let now = Clock::get()?.unix_timestamp;
+
+        // Guardians may have changed since approving, so recount
+        let guardians = &ctx.accounts.multisig.guardians;
+        let approvals = recovery
+            .approvals
+            .iter()
+            .filter(|approver| guardians.contains(approver))
+            .count();
+        require!(
+            recovery.eta != 0
+                && now >= recovery.eta
+                && approvals >= ctx.accounts.multisig.guardian_threshold as usize,
+            ErrorCode::RecoveryNotReady
+        );
+
+        let new_owners = recovery.new_owners.clone();
+        let new_threshold = recovery.new_threshold;
+
+        // min_owners may have been raised while the recovery was pending
+        if new_threshold == 0 || new_threshold as usize > new_owners.len() {
+            msg!("threshold {} must be between 1 and owner count {}", new_threshold, new_owners.len());
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+        require!(
+            new_owners.len() >= ctx.accounts.multisig.min_owners as usize,
+            ErrorCode::TooFewOwners
+        );
+
+        let multisig = &mut ctx.accounts.multisig;
+        let old_threshold = multisig.threshold;
+        multisig.owners = new_owners;
+        multisig.owner_set_seq += 1;
+        multisig.threshold = new_threshold;
+        multisig.threshold_mode = ThresholdMode::Absolute;
+        multisig.threshold_bps = 0;
+        multisig.weights = Vec::new();
+        multisig.weight_threshold = 0;
+        multisig.delegates = Vec::new();
+        multisig.roles = Vec::new();
+        // The new owners hold no roles, so any requirement would lock them out
+        multisig.required_roles = Vec::new();
+        multisig.veto_owners = Vec::new();
+        multisig.pending_nomination = None;
+        // A queued change was approved by the owners being replaced
+        multisig.pending_governance_change = None;
+        if !multisig.approval_counts.is_empty() {
+            multisig.approval_counts = vec![0; multisig.owners.len()];
+        }
+
+        fit_owners(multisig, &ctx.accounts.payer, &ctx.accounts.system_program)?;
+
+        emit!(RecoveryFinalized {
+            multisig: multisig.key(),
+            new_owners: multisig.owners.clone(),
+            new_threshold,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        emit!(OwnershipChanged {
+            multisig: multisig.key(),
+            change_type: OwnershipChangeType::Recovered,
+            target: Pubkey::default(),
+            old_threshold,
+            new_threshold,
+            owner_count: multisig.owners.len() as u8,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    // Any current owner can veto a pending recovery during its delay
+    pub fn cancel_recovery(ctx: Context<CancelRecovery>, _multisig_id: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        require!(
+            ctx.accounts.multisig.owners.contains(&owner),
+            ErrorCode::NotOwner
+        );
+
+        emit!(RecoveryCancelled {
+            multisig: ctx.accounts.multisig.key(),
+            recovery: ctx.accounts.recovery.key(),
+            cancelled_by: owner,
+            event_seq: ctx.accounts.multisig.next_event_seq(),
+        });
+
+        Ok(())
+    }
+}
+
+// Shared by execute_transaction and execute_with_signatures once the caller has
+// collected the approving owners. Bumps are (multisig, vault). Runs the
+// instructions in `chunk`, which must continue where the previous chunk ended;
+// the transaction only counts as executed once the last instruction has run.
+#[allow(clippy::too_many_arguments)]
+fn execute_approved<'info>(
+    multisig: &mut Account<'info, Multisig>,
+    transaction: &mut Account<'info, Transaction>,
+    executor: &Signer<'info>,
+    vault: &SystemAccount<'info>,
+    system_program: &Program<'info, System>,
+    remaining_accounts: &[AccountInfo],
+    vault_bump: u8,
+    approvals: &[Pubkey],
+    chunk: std::ops::Range<usize>,
+) -> Result<ExecutionOutcome> {
+    // A stored instruction calling back into execute_transaction
+    require!(!multisig.executing, ErrorCode::ReentrancyDetected);
+
+    // Check if already executed
+    require!(!transaction.did_execute, ErrorCode::AlreadyExecuted);
+
+    require!(!multisig.paused, ErrorCode::MultisigPaused);
+
+    require!(
+        !multisig.executor_must_be_owner || multisig.owners.contains(executor.key),
+        ErrorCode::ExecutorNotOwner
+    );
+
+    require!(!transaction.vetoed, ErrorCode::Vetoed);
+
+    require!(!transaction.rejected, ErrorCode::TransactionRejected);
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !transaction.execution_expired(multisig.expiry_grace, now),
+        ErrorCode::TransactionExpired
+    );
+
+    require!(
+        transaction.within_slot_window(Clock::get()?.slot),
+        ErrorCode::SlotOutsideWindow
+    );
+
+    // Check if enough approvals
+    require!(
+        multisig.threshold_met_for(transaction, approvals),
+        ErrorCode::NotEnoughApprovals
+    );
+
+    require!(multisig.role_quorum_met(approvals), ErrorCode::RoleQuorumNotMet);
+
+    // Counted over the whole batch, so chunking can't split a transfer past the cap
+    require!(
+        multisig.max_tx_value == 0 || transaction.sol_outflow() <= multisig.max_tx_value,
+        ErrorCode::TransactionValueExceedsLimit
+    );
+
+    // Enforce the cooling-off period since the threshold was reached
+    require!(
+        transaction.timelock_elapsed(multisig.min_delay, now),
+        ErrorCode::TimelockNotElapsed
+    );
+
+    // The dependency's Transaction account must be passed in remaining_accounts.
+    // It can't be closed while pinned, so a missing account means it was cancelled.
+    if let Some(dependency) = transaction.depends_on {
+        let (address, _) = Pubkey::find_program_address(
+            &[b"transaction", multisig.key().as_ref(), &dependency.to_le_bytes()],
+            &crate::ID,
+        );
+        let executed = remaining_accounts
+            .iter()
+            .find(|info| info.key == &address && info.owner == &crate::ID)
+            .and_then(|info| Transaction::try_deserialize(&mut &info.try_borrow_data().ok()?[..]).ok())
+            .is_some_and(|dependency| dependency.did_execute);
+        require!(executed, ErrorCode::DependencyNotExecuted);
+    }
+
+    let total = transaction.instructions.len();
+    require!(
+        chunk.start == transaction.executed_count as usize
+            && chunk.end <= total
+            && (!chunk.is_empty() || total == 0),
+        ErrorCode::InvalidChunk
+    );
+
+    // Every stored key (lookup tables included) and every program has to be among
+    // the supplied accounts. Checked up front so a short list fails clearly
+    // instead of somewhere inside the CPI.
+    let mut required = std::collections::BTreeSet::new();
+    for ix in &transaction.instructions[chunk.clone()] {
+        required.insert(ix.program_id);
+        required.extend(ix.accounts.iter().map(|acc| acc.pubkey));
+    }
+    require!(
+        remaining_accounts.len() >= required.len()
+            && required
+                .iter()
+                .all(|key| remaining_accounts.iter().any(|info| info.key == key)),
+        ErrorCode::MissingExecutionAccounts
+    );
+
+    // Covers every instruction, not just this chunk, so all referenced tables
+    // have to be passed whenever the proposal uses any
+    if transaction.lookup_keys_hash != [0; 32] {
+        require!(
+            hash_lookup_keys(&transaction.instructions, remaining_accounts)? == transaction.lookup_keys_hash,
+            ErrorCode::LookupKeysMismatch
+        );
+    }
+
+    // Circuit breaker: the first attempt at a large outflow pauses the multisig
+    // and records a hold, returning Held rather than an error so both persist.
+    // Nothing executes until a guardian calls confirm_large_outflow, which
+    // unpauses. Past its window an unconfirmed hold can only be cancelled.
+    if transaction.executed_count == 0 && multisig.outflow_needs_confirmation(transaction, vault.lamports()) {
+        require!(
+            multisig.pending_large_tx.is_none_or(|hold| hold.nonce != transaction.nonce),
+            ErrorCode::LargeOutflowNotConfirmed
+        );
+        multisig.pending_large_tx = Some(LargeOutflowHold {
+            nonce: transaction.nonce,
+            held_at: now,
+        });
+        multisig.paused = true;
+
+        emit!(LargeOutflowHeld {
+            multisig: multisig.key(),
+            transaction: transaction.key(),
+            amount: transaction.sol_outflow(),
+            vault_balance: vault.lamports(),
+            confirm_by: now + LARGE_OUTFLOW_WINDOW,
+            event_seq: multisig.next_event_seq(),
+        });
+
+        return Ok(ExecutionOutcome::Held);
+    }
+    if multisig.released_large_tx.is_some_and(|release| release.nonce == transaction.nonce) {
+        multisig.released_large_tx = None;
+    }
+
+    // Record progress before any CPI
+    transaction.executed_count = chunk.end as u8;
+    transaction.did_execute = chunk.end == total;
+
+    // close_multisig can only be the last instruction and leaves nothing to
+    // update afterwards, so the execution is reported before the CPIs. The
+    // vault has to be empty for the close, so there is no reward to pay.
+    let closes_multisig = transaction.did_execute && transaction.instructions.last().is_some_and(is_close_multisig_call);
+    if closes_multisig {
+        emit!(TransactionExecuted {
+            transaction: transaction.key(),
+            executor: executor.key(),
+            reward_paid: 0,
+            priority_hint: transaction.priority_hint,
+            tx_hash: transaction.tx_hash,
+            event_seq: multisig.next_event_seq(),
+        });
+    }
+
+    // Persist the guard before any CPI: a nested call deserializes the multisig
+    // from account data, which Anchor otherwise only writes back on exit
+    multisig.executing = true;
+    multisig.executing_nonce = transaction.nonce;
+    multisig.exit(&crate::ID)?;
+
+    // Fix: Create proper seeds array
+    let multisig_id = multisig.multisig_id.to_le_bytes();
+    let multisig_seeds: &[&[u8]] = &[
+     b"multisig",
+     &multisig_id,
+     &[multisig.bump],
+    ];
+
+    // The vault signs for transfers out of it
+    let multisig_key = multisig.key();
+    let vault_key = vault.key();
+    let vault_seeds: &[&[u8]] = &[b"vault", multisig_key.as_ref(), &[vault_bump]];
+
+    // Execute each stored instruction in order using Cross Program Invocation (CPI).
+    // If any of them fails the whole chunk is rolled back.
+    for ix in &transaction.instructions[chunk.clone()] {
+        require!(
+            multisig.allow_self_calls || ix.program_id != crate::ID,
+            ErrorCode::SelfCallNotAllowed
+        );
+
+        let instruction = resolve_instruction(ix, remaining_accounts)?;
+
+        // The executor must supply exactly the accounts the owners approved
+        verify_instruction_accounts(&instruction, remaining_accounts, &[multisig_key, vault_key])?;
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &instruction,
+            remaining_accounts,
+            &[multisig_seeds, vault_seeds]
+        )?;
+    }
+
+    // close_multisig has torn the wallet down. TransactionExecuted went out
+    // above and did_execute is written back with the transaction on exit.
+    if closes_multisig && multisig.to_account_info().owner != &crate::ID {
+        return Ok(ExecutionOutcome::Executed);
+    }
+
+    // The instructions may have modified the multisig itself (e.g. add_owner),
+    // so reload it before writing to avoid clobbering those changes on exit
+    multisig.reload()?;
+    multisig.executing = false;
+    multisig.last_activity = now;
+
+    if !transaction.did_execute {
+        emit!(TransactionChunkExecuted {
+            transaction: transaction.key(),
+            executor: executor.key(),
+            start_index: chunk.start as u8,
+            count: chunk.len() as u8,
+            remaining: (total - chunk.end) as u8,
+            event_seq: multisig.next_event_seq(),
+        });
+        return Ok(ExecutionOutcome::ChunkExecuted);
+    }
+
+    // Clear transaction data after execution unless the multisig keeps it for
+    // audits; tx_hash in TransactionExecuted fingerprints it either way
+    if !multisig.retain_executed_data {
+        transaction.instructions.clear();
+    }
+    multisig.open_tx_count = multisig.open_tx_count.saturating_sub(1);
+    multisig.open_nonces.retain(|open| *open != transaction.nonce);
+    unpin_dependency(multisig, transaction);
+
+    // Pay the executor from the vault. An underfunded vault doesn't fail the
+    // execution, the reward is simply skipped
+    let vault_info = vault.to_account_info();
+    let vault_floor = Rent::get()?.minimum_balance(0);
+    let reward_paid = if transaction.executor_reward > 0
+        && vault_info.lamports() >= transaction.executor_reward.saturating_add(vault_floor)
+    {
+        invoke_signed(
+            &system_instruction::transfer(
+                &vault_key,
+                &executor.key(),
+                transaction.executor_reward,
+            ),
+            &[
+                vault_info,
+                executor.to_account_info(),
+                system_program.to_account_info(),
+            ],
+            &[vault_seeds],
+        )?;
+        transaction.executor_reward
+    } else {
+        0
+    };
+
+    emit!(TransactionExecuted {
+        transaction: transaction.key(),
+        executor: executor.key(),
+        reward_paid,
+        priority_hint: transaction.priority_hint,
+        tx_hash: transaction.tx_hash,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(ExecutionOutcome::Executed)
+}
+
+// Adds a guardian's approval to a pending recovery and starts the delay once
+// the guardian threshold is reached
+fn record_recovery_approval(
+    multisig: &mut Account<Multisig>,
+    recovery: &mut Account<Recovery>,
+    guardian: Pubkey,
+) -> Result<()> {
+    recovery.approvals.push(guardian);
+
+    if recovery.eta == 0 && recovery.approvals.len() >= multisig.guardian_threshold as usize {
+        recovery.eta = Clock::get()?.unix_timestamp + RECOVERY_DELAY;
+    }
+
+    emit!(RecoveryApproved {
+        multisig: multisig.key(),
+        recovery: recovery.key(),
+        guardian,
+        eta: recovery.eta,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+// Sliding-window circuit breaker: a burst of proposals usually means a
+// compromised proposer key, so the multisig pauses itself. The proposal that
+// trips it is still created.
+fn track_creation_rate(multisig: &mut Account<Multisig>, now: i64) {
+    if multisig.max_tx_per_window == 0 {
+        return;
+    }
+
+    let window_start = now - multisig.window_secs;
+    multisig.recent_creations.retain(|created_at| *created_at > window_start);
+    multisig.recent_creations.push(now);
+
+    let count = multisig.recent_creations.len();
+    if count > multisig.max_tx_per_window as usize {
+        // Keep the vec within its allocation
+        multisig.recent_creations.remove(0);
+
+        if !multisig.paused {
+            multisig.paused = true;
+
+            emit!(AutoPaused {
+                multisig: multisig.key(),
+                count: count as u16,
+                window_secs: multisig.window_secs,
+                event_seq: multisig.next_event_seq(),
+            });
+        }
+    }
+}
+
+// Reallocs `info` to `new_space` if it is smaller, with `payer` covering the rent
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    new_space: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    if new_space > info.data_len() {
+        let rent = Rent::get()?.minimum_balance(new_space).saturating_sub(info.lamports());
+        if rent > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                rent,
+            )?;
+        }
+        info.resize(new_space)?;
+    }
+
+    Ok(())
+}
+
+// Returns `space` if `payer` can cover its rent-exempt minimum. Used inside init's
+// space expression so an underfunded proposer gets InsufficientRentFunds, with
+// the balance and required amount logged, instead of a system program error.
+fn rent_funded_space(payer: &AccountInfo, space: usize) -> Result<usize> {
+    let required = Rent::get()?.minimum_balance(space);
+    if payer.lamports() < required {
+        return Err(error!(ErrorCode::InsufficientRentFunds).with_values((payer.lamports(), required)));
+    }
+    Ok(space)
+}
+
+// init_if_needed's space for create_transaction_idempotent. An existing proposal
+// keeps its size (a migrate may have changed max_data_size) and costs no rent, so a
+// retry isn't refused over the balance the first attempt spent; a new one is
+// checked like create_transaction's.
+fn idempotent_space(payer: &AccountInfo, transaction: &AccountInfo, space: usize) -> Result<usize> {
+    if transaction.owner == &crate::ID {
+        return Ok(transaction.data_len());
+    }
+    rent_funded_space(payer, space)
+}
+
+// Allocates a PDA owned by `owner` the way Anchor's init does, so lamports
+// sent to the address beforehand can't block its creation
+fn create_pda_account<'info>(
+    info: &AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    use anchor_lang::system_program::{allocate, assign, create_account, transfer};
+    use anchor_lang::system_program::{Allocate, Assign, CreateAccount, Transfer};
+
+    let rent = Rent::get()?.minimum_balance(space);
+    let program = system_program.to_account_info();
+    if info.lamports() == 0 {
+        return create_account(
+            CpiContext::new_with_signer(
+                program,
+                CreateAccount { from: payer.to_account_info(), to: info.clone() },
+                &[seeds],
+            ),
+            rent,
+            space as u64,
+            owner,
+        );
+    }
+
+    require!(info.owner == &anchor_lang::system_program::ID, ErrorCode::NonceAlreadyUsed);
+    let shortfall = rent.saturating_sub(info.lamports());
+    if shortfall > 0 {
+        transfer(
+            CpiContext::new(program.clone(), Transfer { from: payer.to_account_info(), to: info.clone() }),
+            shortfall,
+        )?;
+    }
+    allocate(
+        CpiContext::new_with_signer(program.clone(), Allocate { account_to_allocate: info.clone() }, &[seeds]),
+        space as u64,
+    )?;
+    assign(
+        CpiContext::new_with_signer(program, Assign { account_to_assign: info.clone() }, &[seeds]),
+        owner,
+    )
+}
+
+// Stores a membership, threshold or spending-limit change to be applied by
+// apply_governance_change after min_governance_delay. Only one can be pending.
+fn queue_governance_change(multisig: &mut Account<Multisig>, action: GovernanceAction) -> Result<()> {
+    require!(
+        multisig.pending_governance_change.is_none(),
+        ErrorCode::GovernanceChangePending
+    );
+
+    let eta = Clock::get()?.unix_timestamp + multisig.min_governance_delay;
+    multisig.pending_governance_change = Some(GovernanceChange { action, eta });
+
+    emit!(GovernanceChangeQueued {
+        multisig: multisig.key(),
+        action,
+        eta,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+fn apply_daily_limit(multisig: &mut Account<Multisig>, daily_limit: u64) -> Result<()> {
+    let old_limit = multisig.daily_limit;
+    multisig.daily_limit = daily_limit;
+
+    emit!(DailyLimitChanged {
+        multisig: multisig.key(),
+        old_limit,
+        new_limit: daily_limit,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+fn apply_max_tx_value(multisig: &mut Account<Multisig>, max_tx_value: u64) -> Result<()> {
+    multisig.max_tx_value = max_tx_value;
+
+    emit!(MaxTxValueChanged {
+        multisig: multisig.key(),
+        max_tx_value,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+fn apply_change_threshold(multisig: &mut Account<Multisig>, new_threshold: u8) -> Result<()> {
+    if new_threshold == 0 || new_threshold as u64 > multisig.total_weight() {
+        msg!("threshold {} must be between 1 and total weight {}", new_threshold, multisig.total_weight());
+        return Err(ErrorCode::InvalidThreshold.into());
+    }
+
+    let old_threshold = multisig.threshold;
+    multisig.threshold = new_threshold;
+    multisig.weight_threshold = 0;
+    // Setting an absolute threshold leaves percentage mode
+    multisig.threshold_mode = ThresholdMode::Absolute;
+
+    emit!(ThresholdChanged {
+        multisig: multisig.key(),
+        old_threshold,
+        new_threshold,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    emit!(OwnershipChanged {
+        multisig: multisig.key(),
+        change_type: OwnershipChangeType::ThresholdChanged,
+        target: Pubkey::default(),
+        old_threshold,
+        new_threshold,
+        owner_count: multisig.owners.len() as u8,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+fn apply_weight_threshold(multisig: &mut Account<Multisig>, weight_threshold: u64) -> Result<()> {
+    if weight_threshold == 0 || weight_threshold > multisig.total_weight() {
+        msg!("threshold {} must be between 1 and total weight {}", weight_threshold, multisig.total_weight());
+        return Err(ErrorCode::InvalidThreshold.into());
+    }
+
+    let old_weight_threshold = multisig.weight_threshold;
+    multisig.weight_threshold = weight_threshold;
+    multisig.threshold_mode = ThresholdMode::Absolute;
+
+    emit!(WeightThresholdChanged {
+        multisig: multisig.key(),
+        old_weight_threshold,
+        weight_threshold,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+fn apply_threshold_bps(multisig: &mut Account<Multisig>, threshold_bps: u16) -> Result<()> {
+    require!(
+        (1..=BPS_DENOMINATOR).contains(&threshold_bps),
+        ErrorCode::InvalidThresholdBps
+    );
+
+    multisig.threshold_mode = ThresholdMode::Percentage;
+    multisig.threshold_bps = threshold_bps;
+
+    emit!(ThresholdBpsChanged {
+        multisig: multisig.key(),
+        threshold_bps,
+        required_weight: multisig.required_weight(),
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+fn apply_nominate_owner(multisig: &mut Account<Multisig>, candidate: Pubkey) -> Result<()> {
+    require!(!multisig.owners.contains(&candidate), ErrorCode::AlreadyAnOwner);
+
+    let expires_at = Clock::get()?.unix_timestamp + NOMINATION_WINDOW;
+    multisig.pending_nomination = Some(OwnerNomination { candidate, expires_at });
+
+    emit!(OwnerNominated {
+        multisig: multisig.key(),
+        candidate,
+        expires_at,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+fn apply_add_owner<'info>(
+    multisig: &mut Account<'info, Multisig>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    new_owner: Pubkey,
+) -> Result<()> {
+    require!(
+        !multisig.owners.contains(&new_owner),
+        ErrorCode::AlreadyAnOwner
+    );
+
+    require!(
+        multisig.owners.len() < MAX_OWNERS,
+        ErrorCode::TooManyOwners
+    );
+
+    multisig.owners.push(new_owner);
+    // An owner can't also be a delegate
+    multisig.delegates.retain(|delegation| delegation.delegate != new_owner);
+
+    // New owners get a single vote in weighted mode
+    if !multisig.weights.is_empty() {
+        multisig.weights.push(1);
+    }
+    if !multisig.roles.is_empty() {
+        multisig.roles.push([0; 16]);
+    }
+    if !multisig.approval_counts.is_empty() {
+        multisig.approval_counts.push(0);
+    }
+
+    fit_owners(multisig, payer, system_program)?;
+
+    emit!(OwnerAdded {
+        multisig: multisig.key(),
+        owner: new_owner,
+        owners_count: multisig.owners.len() as u8,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    emit!(OwnershipChanged {
+        multisig: multisig.key(),
+        change_type: OwnershipChangeType::Added,
+        target: new_owner,
+        old_threshold: multisig.threshold,
+        new_threshold: multisig.threshold,
+        owner_count: multisig.owners.len() as u8,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+fn apply_replace_owner(multisig: &mut Account<Multisig>, old_owner: Pubkey, new_owner: Pubkey) -> Result<()> {
+    require!(
+        !multisig.owners.contains(&new_owner),
+        ErrorCode::AlreadyAnOwner
+    );
+
+    let index = multisig
+        .owners
+        .iter()
+        .position(|owner| *owner == old_owner)
+        .ok_or(ErrorCode::NotOwner)?;
+    multisig.owners[index] = new_owner;
+    multisig.owner_set_seq += 1;
+    // The count describes the old key, the new one starts from zero
+    if let Some(count) = multisig.approval_counts.get_mut(index) {
+        *count = 0;
+    }
+    multisig
+        .delegates
+        .retain(|delegation| delegation.owner != old_owner && delegation.delegate != new_owner);
+    // Like the slot and weight, veto power passes to the new key
+    for veto_owner in multisig.veto_owners.iter_mut().filter(|veto_owner| **veto_owner == old_owner) {
+        *veto_owner = new_owner;
+    }
+    require!(multisig.role_quorum_reachable(), ErrorCode::RoleQuorumUnreachable);
+
+    emit!(OwnerReplaced {
+        multisig: multisig.key(),
+        old_owner,
+        new_owner,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    emit!(OwnershipChanged {
+        multisig: multisig.key(),
+        change_type: OwnershipChangeType::Replaced,
+        target: new_owner,
+        old_threshold: multisig.threshold,
+        new_threshold: multisig.threshold,
+        owner_count: multisig.owners.len() as u8,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+fn apply_remove_owner(
+    multisig: &mut Account<Multisig>,
+    owner_to_remove: Pubkey,
+    new_threshold: Option<u8>,
+) -> Result<()> {
+    let index = multisig
+        .owners
+        .iter()
+        .position(|owner| *owner == owner_to_remove)
+        .ok_or(ErrorCode::NotOwner)?;
+    multisig.owners.remove(index);
+    multisig.owner_set_seq += 1;
+    if !multisig.weights.is_empty() {
+        multisig.weights.remove(index);
+    }
+    if !multisig.roles.is_empty() {
+        multisig.roles.remove(index);
+    }
+    if !multisig.approval_counts.is_empty() {
+        multisig.approval_counts.remove(index);
+    }
+    multisig.delegates.retain(|delegation| delegation.owner != owner_to_remove);
+    multisig.veto_owners.retain(|veto_owner| *veto_owner != owner_to_remove);
+
+    let old_threshold = multisig.threshold;
+    let threshold = new_threshold.unwrap_or(old_threshold);
+    multisig.threshold = threshold;
+    // A new threshold replaces a weight_threshold
+    if new_threshold.is_some() {
+        multisig.weight_threshold = 0;
+    }
+
+    // Threshold must still be reachable by the remaining owners. A
+    // percentage threshold scales down with them.
+    let required_weight = multisig.required_weight();
+    if multisig.threshold_mode != ThresholdMode::Percentage
+        && (required_weight == 0 || required_weight > multisig.total_weight())
+    {
+        msg!("threshold {} must be between 1 and remaining weight {}", required_weight, multisig.total_weight());
+        return Err(ErrorCode::InvalidThreshold.into());
+    }
+    require!(!multisig.owners.is_empty(), ErrorCode::NoOwners);
+    require!(
+        multisig.owners.len() >= multisig.min_owners as usize,
+        ErrorCode::TooFewOwners
+    );
+    require!(multisig.role_quorum_reachable(), ErrorCode::RoleQuorumUnreachable);
+
+    emit!(OwnerRemoved {
+        multisig: multisig.key(),
+        owner: owner_to_remove,
+        owners_count: multisig.owners.len() as u8,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    if threshold != old_threshold {
+        emit!(ThresholdChanged {
+            multisig: multisig.key(),
+            old_threshold,
+            new_threshold: threshold,
+            event_seq: multisig.next_event_seq(),
+        });
+    }
+
+    emit!(OwnershipChanged {
+        multisig: multisig.key(),
+        change_type: OwnershipChangeType::Removed,
+        target: owner_to_remove,
+        old_threshold,
+        new_threshold: threshold,
+        owner_count: multisig.owners.len() as u8,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+fn apply_governance_delay(multisig: &mut Account<Multisig>, min_governance_delay: i64) -> Result<()> {
+    let old_delay = multisig.min_governance_delay;
+    multisig.min_governance_delay = min_governance_delay;
+
+    emit!(GovernanceDelayChanged {
+        multisig: multisig.key(),
+        old_delay,
+        new_delay: min_governance_delay,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+// Grows the multisig account, topped up by `payer`, when its owners vec no
+// longer fits the current allocation
+fn fit_owners<'info>(
+    multisig: &mut Account<'info, Multisig>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    grow_account(
+        &multisig.to_account_info(),
+        Multisig::space(multisig.owners.len()),
+        payer,
+        system_program,
+    )?;
+
+    // Make sure the grown owners vec actually fits the allocation
+    let required_space = 8 + anchor_lang::prelude::borsh::to_vec(&**multisig)?.len();
+    require!(
+        required_space <= multisig.to_account_info().data_len(),
+        ErrorCode::TooManyOwners
+    );
+
+    Ok(())
+}
+
+// Checks shared by every instruction that creates a proposal
+fn validate_proposal(
+    multisig: &Multisig,
+    proposer: &Pubkey,
+    nonce: u64,
+    instructions: &[TransactionInstruction],
+    expires_at: i64,
+) -> Result<()> {
+    require!(!multisig.paused, ErrorCode::MultisigPaused);
+
+    require!(
+        multisig.owners.contains(proposer),
+        ErrorCode::NotAnOwner
+    );
+
+    if multisig.legacy_nonces {
+        if multisig.used_nonces.contains(&nonce) {
+            msg!("nonce {} already used", nonce);
+            return Err(ErrorCode::NonceAlreadyUsed.into());
+        }
+
+        // Evicting old nonces would make them replayable, so they have to be
+        // freed explicitly with release_nonce or cancel_transaction
+        require!(
+            multisig.used_nonces.len() < MAX_STORED_NONCES,
+            ErrorCode::NonceBufferFull
+        );
+    } else {
+        // The counter makes every transaction PDA unique without storing nonces
+        if nonce != multisig.seq {
+            msg!("nonce {} does not match the expected nonce {}", nonce, multisig.seq);
+            return Err(ErrorCode::UnexpectedNonce.into());
+        }
+    }
+
+    require!(
+        multisig.open_tx_count < MAX_OPEN_TRANSACTIONS,
+        ErrorCode::TooManyOpenTransactions
+    );
+
+    // 0 means the proposal never expires
+    require!(
+        expires_at == 0 || expires_at > Clock::get()?.unix_timestamp,
+        ErrorCode::TransactionExpired
+    );
+
+    // Validate instruction limits
+    if instructions.len() > MAX_INSTRUCTIONS {
+        msg!("{} instructions exceed the maximum of {}", instructions.len(), MAX_INSTRUCTIONS);
+        return Err(ErrorCode::TooManyInstructions.into());
+    }
+
+    for (position, ix) in instructions.iter().enumerate() {
+        // This program is exempt so the list can always be edited again;
+        // self-calls are still subject to allow_self_calls at execution
+        require!(
+            multisig.allowed_programs.is_empty()
+                || ix.program_id == crate::ID
+                || multisig.allowed_programs.contains(&ix.program_id),
+            ErrorCode::ProgramNotAllowed
+        );
+
+        require!(!is_self_transfer(ix), ErrorCode::SelfTransferNotAllowed);
+
+        // Nothing may run on a closed multisig
+        require!(
+            position + 1 == instructions.len() || !is_close_multisig_call(ix),
+            ErrorCode::CloseMultisigNotLast
+        );
+
+        let max_accounts = if ix.accounts.iter().any(|acc| acc.lookup_index.is_some()) {
+            MAX_LOOKUP_INSTRUCTION_ACCOUNTS
+        } else {
+            MAX_INSTRUCTION_ACCOUNTS
+        };
+        if ix.accounts.len() > max_accounts {
+            msg!(
+                "instruction {} has {} accounts, the maximum is {}",
+                position,
+                ix.accounts.len(),
+                max_accounts
+            );
+            return Err(ErrorCode::TooManyAccounts.into());
+        }
+
+        // A repeated account must at least carry the same flags every time,
+        // unless the multisig refuses repeats altogether
+        for (index, acc) in ix.accounts.iter().enumerate() {
+            for other in &ix.accounts[..index] {
+                if other.pubkey != acc.pubkey || other.lookup_index != acc.lookup_index {
+                    continue;
+                }
+
+                require!(
+                    !multisig.reject_duplicate_accounts,
+                    ErrorCode::DuplicateInstructionAccount
+                );
+                require!(
+                    other.is_signer == acc.is_signer && other.is_writable == acc.is_writable,
+                    ErrorCode::InconsistentAccountFlags
+                );
+            }
+        }
+
+        if ix.data.len() > multisig.max_data_size as usize {
+            msg!(
+                "instruction {} has {} bytes of data, the maximum is {}",
+                position,
+                ix.data.len(),
+                multisig.max_data_size
+            );
+            return Err(ErrorCode::InstructionDataTooLarge.into());
+        }
+    }
+
+    // Transaction::space reserves MAX_INSTRUCTION_ACCOUNTS per instruction
+    let total_accounts: usize = instructions.iter().map(|ix| ix.accounts.len()).sum();
+    if total_accounts > MAX_INSTRUCTIONS * MAX_INSTRUCTION_ACCOUNTS {
+        msg!(
+            "{} accounts in total, the maximum is {}",
+            total_accounts,
+            MAX_INSTRUCTIONS * MAX_INSTRUCTION_ACCOUNTS
+        );
+        return Err(ErrorCode::TooManyAccounts.into());
+    }
+
+    Ok(())
+}
+
+// Releases one pin on the proposal's dependency once it is no longer open
+fn unpin_dependency(multisig: &mut Multisig, transaction: &Transaction) {
+    let Some(dependency) = transaction.depends_on else {
+        return;
+    };
+    if let Some(index) = multisig.pinned_nonces.iter().position(|pinned| *pinned == dependency) {
+        multisig.pinned_nonces.remove(index);
+    }
+}
+
+// Writes a freshly initialized proposal and marks its nonce as used
+fn record_proposal(
+    multisig: &mut Account<Multisig>,
+    transaction: &mut Account<Transaction>,
+    proposer: Pubkey,
+    nonce: u64,
+    instructions: Vec<TransactionInstruction>,
+    mut terms: ProposalTerms,
+) -> Result<()> {
+    // An explicit expiry overrides the multisig's default lifetime
+    let now = Clock::get()?.unix_timestamp;
+    if terms.expires_at == 0 && multisig.default_tx_ttl > 0 {
+        terms.expires_at = now + multisig.default_tx_ttl;
+    }
+
+    // The dependency stays pinned until this proposal executes or is cancelled
+    if let Some(dependency) = terms.depends_on {
+        if dependency == nonce || !multisig.open_nonces.contains(&dependency) {
+            msg!("depends_on {} is not another open proposal", dependency);
+            return Err(ErrorCode::InvalidDependency.into());
+        }
+        multisig.pinned_nonces.push(dependency);
+    }
+
+    transaction.multisig = multisig.key();
+    transaction.proposer = proposer;
+    transaction.approvals = Vec::new();
+    transaction.rejections = Vec::new();
+    transaction.rejected = false;
+    transaction.executed_count = 0;
+    transaction.did_execute = false;
+    transaction.nonce = nonce;
+    transaction.tx_hash = hash_proposal(&instructions, &terms)?;
+    transaction.read_only = instructions
+        .iter()
+        .all(|ix| ix.accounts.iter().all(|acc| !acc.is_writable));
+    transaction.instructions = instructions;
+    transaction.eta = 0;
+    transaction.executor_reward = terms.executor_reward;
+    transaction.expires_at = terms.expires_at;
+    transaction.memos = Vec::new();
+    transaction.depends_on = terms.depends_on;
+    transaction.priority_hint = PriorityHint::Low;
+    transaction.description = terms.description;
+    transaction.valid_from_slot = terms.valid_from_slot;
+    transaction.valid_until_slot = terms.valid_until_slot;
+    transaction.vetoed = false;
+    transaction.required_approvals = terms.required_approvals;
+    transaction.approval_bitmap = 0;
+    transaction.owner_set_seq = multisig.owner_set_seq;
+    transaction.approval_times = Vec::new();
+    transaction.lookup_keys_hash = terms.lookup_keys_hash;
+
+    multisig.last_activity = now;
+    multisig.open_tx_count += 1;
+    multisig.open_nonces.push(nonce);
+    track_creation_rate(multisig, now);
+
+    if multisig.legacy_nonces {
+        // validate_proposal guarantees there is room
+        multisig.used_nonces.push(nonce);
+    } else {
+        multisig.seq += 1;
+    }
+
+    emit!(TransactionCreated {
+        multisig: multisig.key(),
+        transaction: transaction.key(),
+        proposer,
+        nonce,
+        tx_hash: transaction.tx_hash,
+        expires_at: transaction.expires_at,
+        description: transaction.description.clone(),
+        event_seq: multisig.next_event_seq(),
+    });
+
+    Ok(())
+}
+
+// Deserializes a durable nonce account and returns its authority, rejecting
+// anything that isn't an initialized nonce account owned by the system program
+fn nonce_authority(nonce_account: &AccountInfo) -> Result<Pubkey> {
+    require_keys_eq!(
+        *nonce_account.owner,
+        anchor_lang::system_program::ID,
+        ErrorCode::InvalidNonceAccount
+    );
+
+    let data = nonce_account.try_borrow_data()?;
+    let versions: NonceVersions = bincode::deserialize(&data)
+        .map_err(|_| ErrorCode::InvalidNonceAccount)?;
+
+    match versions.state() {
+        NonceState::Initialized(nonce_data) => Ok(nonce_data.authority),
+        NonceState::Uninitialized => Err(ErrorCode::InvalidNonceAccount.into()),
+    }
+}
+
+// Adds an approval that has already been validated and emits the resulting events
+fn record_approval(
+    multisig: &mut Multisig,
+    transaction: &mut Account<Transaction>,
+    owner: Pubkey,
+    now: i64,
+) {
+    // A vote in favour replaces an earlier rejection
+    transaction.rejections.retain(|rejecter| *rejecter != owner);
+
+    let was_below_threshold = !multisig.threshold_met_for(transaction, &transaction.approvers(multisig));
+    transaction.set_approval(multisig, &owner, now);
+    if let Some(index) = multisig.owner_index(&owner) {
+        if let Some(count) = multisig.approval_counts.get_mut(index) {
+            *count += 1;
+        }
+    }
+    let threshold_met = multisig.threshold_met_for(transaction, &transaction.approvers(multisig));
+
+    emit!(TransactionApproved {
+        transaction: transaction.key(),
+        approver: owner,
+        approvals_count: transaction.approvals_count(multisig),
+        threshold: multisig.threshold,
+        approved_at: now,
+        event_seq: multisig.next_event_seq(),
+    });
+
+    // Fires only on the approval that crosses the threshold
+    if was_below_threshold && threshold_met {
+        emit!(ThresholdReached {
+            transaction: transaction.key(),
+            nonce: transaction.nonce,
+            approvals_count: transaction.approvals_count(multisig),
+            event_seq: multisig.next_event_seq(),
+        });
+    }
+
+    // Start the timelock once the threshold is reached
+    if transaction.eta == 0 && threshold_met {
+        transaction.eta = now;
+
+        emit!(TransactionQueued {
+            transaction: transaction.key(),
+            eta: transaction.eta,
+            executable_at: transaction.eta + multisig.min_delay,
+            event_seq: multisig.next_event_seq(),
+        });
+    }
+}
+
+// Debug builds only: the account's current contents must fit its allocation,
+// catching a space() that lags behind the struct or a limit it doesn't account for
+fn debug_assert_fits<T>(account: &Account<T>)
+where
+    T: AccountSerialize + AccountDeserialize + AnchorSerialize + Owner + Clone,
+{
+    debug_assert!(
+        8 + anchor_lang::prelude::borsh::to_vec(&**account).map_or(usize::MAX, |bytes| bytes.len())
+            <= account.to_account_info().data_len(),
+        "account data exceeds its allocated space"
+    );
+}
+
+// Id a multisig is created under: the caller's choice, or with a seed the first
+// 8 bytes (LE) of SHA-256(creator || seed), so ids never need global coordination
+pub fn resolve_multisig_id(multisig_id: u64, creator: &Pubkey, id_seed: Option<&str>) -> u64 {
+    match id_seed {
+        Some(seed) => {
+            let hash = anchor_lang::solana_program::hash::hashv(&[creator.as_ref(), seed.as_bytes()]);
+            u64::from_le_bytes(hash.to_bytes()[..8].try_into().unwrap())
+        }
+        None => multisig_id,
+    }
+}
+
+// Either bound may be 0 (open); when both are set from must not exceed until
+fn validate_slot_window(valid_from_slot: u64, valid_until_slot: u64) -> Result<()> {
+    require!(
+        valid_until_slot == 0 || valid_from_slot <= valid_until_slot,
+        ErrorCode::InvalidSlotWindow
+    );
+    Ok(())
+}
+
+// A per-proposal override can only be met if the owners can reach it
+fn validate_required_approvals(multisig: &Multisig, required_approvals: u8) -> Result<()> {
+    if required_approvals as u64 > multisig.total_weight() {
+        msg!(
+            "required approvals {} exceed total weight {}",
+            required_approvals,
+            multisig.total_weight()
+        );
+        return Err(ErrorCode::InvalidThreshold.into());
+    }
+    Ok(())
+}
+
+// The vault and multisig PDAs may only sign calls back into this program
+fn validate_pda_signers(multisig: &Pubkey, instructions: &[TransactionInstruction], program_id: &Pubkey) -> Result<()> {
+    let (vault, _) = Pubkey::find_program_address(&[b"vault", multisig.as_ref()], program_id);
+
+    for (position, ix) in instructions.iter().enumerate() {
+        if ix.program_id == *program_id {
+            continue;
+        }
+        for acc in ix.accounts.iter().filter(|acc| acc.is_signer) {
+            if acc.lookup_index.is_some() || acc.pubkey == vault || acc.pubkey == *multisig {
+                msg!("instruction {} asks {} to sign", position, acc.pubkey);
+                return Err(ErrorCode::PdaSignerNotAllowed.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+// Lamports moved by the system program transfers among the instructions
+fn sol_transfer_total(instructions: &[TransactionInstruction]) -> u64 {
+    instructions
+        .iter()
+        .filter(|ix| ix.program_id == anchor_lang::system_program::ID)
+        .filter_map(|ix| match bincode::deserialize::<SystemInstruction>(&ix.data).ok()? {
+            SystemInstruction::Transfer { lamports }
+            | SystemInstruction::TransferWithSeed { lamports, .. } => Some(lamports),
+            _ => None,
+        })
+        .fold(0u64, |total, lamports| total.saturating_add(lamports))
+}
+
+// A stored call to this program's close_multisig
+fn is_close_multisig_call(ix: &TransactionInstruction) -> bool {
+    ix.program_id == crate::ID && ix.data.starts_with(crate::instruction::CloseMultisig::DISCRIMINATOR)
+}
+
+// A system transfer whose source and destination are the same account, which
+// only burns compute and muddles vault flow accounting
+fn is_self_transfer(ix: &TransactionInstruction) -> bool {
+    if ix.program_id != anchor_lang::system_program::ID {
+        return false;
+    }
+    let (from, to) = match bincode::deserialize::<SystemInstruction>(&ix.data) {
+        Ok(SystemInstruction::Transfer { .. }) => (ix.accounts.first(), ix.accounts.get(1)),
+        Ok(SystemInstruction::TransferWithSeed { .. }) => (ix.accounts.first(), ix.accounts.get(2)),
+        _ => return false,
+    };
+    match (from, to) {
+        (Some(from), Some(to)) => from.pubkey == to.pubkey && from.lookup_index == to.lookup_index,
+        _ => false,
+    }
+}
+
+// SHA-256 over the Borsh encoding of the instructions (the length-prefixed
+// program_id || accounts || data of each, in order) followed by the Borsh
+// encoding of the terms, so approvals cover everything execution depends on
+fn hash_proposal(instructions: &[TransactionInstruction], terms: &ProposalTerms) -> Result<[u8; 32]> {
+    let mut bytes = anchor_lang::prelude::borsh::to_vec(instructions)?;
+    bytes.extend(anchor_lang::prelude::borsh::to_vec(terms)?);
+    Ok(anchor_lang::solana_program::hash::hash(&bytes).to_bytes())
+}
+
+// Address of a stored account, reading lookup table entries from the supplied
+// account infos. None if the table is missing, not a lookup table or too short.
+// Execution checks the result against lookup_keys_hash, see hash_lookup_keys.
+fn resolve_account(acc: &TransactionAccount, account_infos: &[AccountInfo]) -> Option<Pubkey> {
+    match acc.reference() {
+        TransactionAccountRef::Key(pubkey) => Some(pubkey),
+        TransactionAccountRef::Lookup { table, index } => {
+            let info = account_infos.iter().find(|info| info.key == &table)?;
+            if *info.owner != ADDRESS_LOOKUP_TABLE_PROGRAM_ID {
+                return None;
+            }
+
+            let data = info.try_borrow_data().ok()?;
+            let start = LOOKUP_TABLE_META_SIZE + 32 * index as usize;
+            data.get(start..start + 32).and_then(|bytes| Pubkey::try_from(bytes).ok())
+        }
+    }
+}
+
+// SHA-256 over the addresses every lookup table reference in `instructions`
+// resolves to, in order; [0; 32] when there are none. Taken at proposal time, so
+// each referenced entry has to exist then, and compared again before execution,
+// so an approved reference can't come to name a different address.
+fn hash_lookup_keys(instructions: &[TransactionInstruction], account_infos: &[AccountInfo]) -> Result<[u8; 32]> {
+    let mut keys = Vec::new();
+    for acc in instructions.iter().flat_map(|ix| ix.accounts.iter()) {
+        if acc.lookup_index.is_some() {
+            let pubkey = resolve_account(acc, account_infos).ok_or(ErrorCode::InvalidLookupTable)?;
+            keys.extend_from_slice(pubkey.as_ref());
+        }
+    }
+    if keys.is_empty() {
+        return Ok([0; 32]);
+    }
+    Ok(anchor_lang::solana_program::hash::hash(&keys).to_bytes())
+}
+
+// Builds the CPI instruction with every lookup table reference resolved
+fn resolve_instruction(
+    ix: &TransactionInstruction,
+    account_infos: &[AccountInfo],
+) -> Result<anchor_lang::solana_program::instruction::Instruction> {
+    let accounts = ix
+        .accounts
+        .iter()
+        .map(|acc| {
+            let pubkey = resolve_account(acc, account_infos).ok_or(ErrorCode::InvalidLookupTable)?;
+            Ok(anchor_lang::solana_program::instruction::AccountMeta {
+                pubkey,
+                is_signer: acc.is_signer,
+                is_writable: acc.is_writable,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(anchor_lang::solana_program::instruction::Instruction {
+        program_id: ix.program_id,
+        accounts,
+        data: ix.data.clone(),
+    })
+}
+
+// Every account in the approved instruction must be present in the supplied
+// account infos with at least the approved privileges. PDAs this program signs
+// for are exempt from the signer check since invoke_signed provides it.
+fn verify_instruction_accounts(
+    ix: &anchor_lang::solana_program::instruction::Instruction,
+    account_infos: &[AccountInfo],
+    pda_signers: &[Pubkey],
+) -> Result<()> {
+    for acc in &ix.accounts {
+        let info = account_infos
+            .iter()
+            .find(|info| info.key == &acc.pubkey)
+            .ok_or(ErrorCode::AccountMismatch)?;
+
+        require!(
+            !acc.is_writable || info.is_writable,
+            ErrorCode::AccountMismatch
+        );
+
+        require!(
+            !acc.is_signer || info.is_signer || pda_signers.contains(&acc.pubkey),
+            ErrorCode::AccountMismatch
+        );
+    }
+
+    Ok(())
+}
+
+// The bytes an owner signs to approve a proposal off-chain:
+// domain tag || multisig || nonce (LE) || tx_hash
+fn build_approval_message(multisig: &Pubkey, nonce: u64, tx_hash: &[u8; 32]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(APPROVAL_DOMAIN.len() + 72);
+    message.extend_from_slice(APPROVAL_DOMAIN);
+    message.extend_from_slice(multisig.as_ref());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message.extend_from_slice(tx_hash);
+    message
+}
+
+// Looks for an ed25519 precompile instruction before the current one that
+// verified `sig` over `message`. Only self-contained entries (all offsets
+// pointing into the precompile instruction itself) are accepted.
+fn ed25519_verified(instructions_sysvar: &AccountInfo, sig: &Ed25519Approval, message: &[u8]) -> Result<bool> {
+    // Layout: num_signatures u8, padding u8, then 14-byte offset entries of
+    // seven u16s: signature, signature ix, pubkey, pubkey ix, message, message size, message ix
+    const HEADER: usize = 2;
+    const ENTRY: usize = 14;
+
+    let current = load_current_index_checked(instructions_sysvar)?;
+    for index in 0..current {
+        let ix = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        if ix.program_id != ed25519_program::ID || ix.data.len() < HEADER {
+            continue;
+        }
+
+        let data = &ix.data;
+        let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]) as usize;
+        let slice = |offset: usize, len: usize| data.get(offset..offset + len);
+
+        for entry in 0..data[0] as usize {
+            let at = HEADER + entry * ENTRY;
+            if data.len() < at + ENTRY {
+                break;
+            }
+
+            let self_contained = [at + 2, at + 6, at + 12]
+                .iter()
+                .all(|&field| read_u16(field) == u16::MAX as usize);
+            if !self_contained {
+                continue;
+            }
+
+            let signature = slice(read_u16(at), 64);
+            let pubkey = slice(read_u16(at + 4), 32);
+            let signed = slice(read_u16(at + 8), read_u16(at + 10));
+
+            if signature == Some(&sig.signature[..])
+                && pubkey == Some(sig.owner.as_ref())
+                && signed == Some(message)
+            {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+// Non-failing counterpart of verify_instruction_accounts used by
+// simulate_transaction, with extra checks that only make sense as hints
+fn simulate_instruction(
+    ix: &TransactionInstruction,
+    account_infos: &[AccountInfo],
+    pda_signers: &[Pubkey],
+) -> Option<(SimulationCode, Pubkey)> {
+    let find = |key: &Pubkey| account_infos.iter().find(|info| info.key == key);
+
+    match find(&ix.program_id) {
+        None => return Some((SimulationCode::MissingProgram, ix.program_id)),
+        Some(program) if !program.executable => {
+            return Some((SimulationCode::ProgramNotExecutable, ix.program_id))
+        }
+        _ => {}
+    }
+
+    for acc in &ix.accounts {
+        let Some(pubkey) = resolve_account(acc, account_infos) else {
+            return Some((SimulationCode::InvalidLookupTable, acc.pubkey));
+        };
+
+        let Some(info) = find(&pubkey) else {
+            return Some((SimulationCode::MissingAccount, pubkey));
+        };
+
+        if (acc.is_writable && !info.is_writable)
+            || (acc.is_signer && !info.is_signer && !pda_signers.contains(&pubkey))
+        {
+            return Some((SimulationCode::PrivilegeMismatch, pubkey));
+        }
+
+        // Writable accounts may legitimately be created by the instruction itself
+        if !acc.is_writable && info.lamports() == 0 {
+            return Some((SimulationCode::AccountNotFound, pubkey));
+        }
+
+        // Owners we can infer: system transfers are funded by system accounts and
+        // token instructions write to token program accounts
+        let expected_owner = if ix.program_id == anchor_lang::system_program::ID && acc.is_signer {
+            Some(anchor_lang::system_program::ID)
+        } else if ix.program_id == TOKEN_PROGRAM_ID && acc.is_writable {
+            Some(TOKEN_PROGRAM_ID)
+        } else {
+            None
+        };
+        if let Some(owner) = expected_owner {
+            if info.lamports() > 0 && *info.owner != owner {
+                return Some((SimulationCode::UnexpectedOwner, pubkey));
+            }
+        }
+    }
+
+    None
+}
+
+fn associated_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), TOKEN_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+// SPL Token TransferChecked (instruction tag 12), built by hand so the
+// program doesn't need to depend on spl-token
+fn token_transfer_checked(
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> TransactionInstruction {
+    let mut data = Vec::with_capacity(10);
+    data.push(12);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+
+    TransactionInstruction {
+        program_id: TOKEN_PROGRAM_ID,
+        accounts: vec![
+            TransactionAccount { pubkey: *source, is_signer: false, is_writable: true, lookup_index: None },
+            TransactionAccount { pubkey: *mint, is_signer: false, is_writable: false, lookup_index: None },
+            TransactionAccount { pubkey: *destination, is_signer: false, is_writable: true, lookup_index: None },
+            TransactionAccount { pubkey: *authority, is_signer: true, is_writable: false, lookup_index: None },
+        ],
+        data,
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(
+    multisig_id: u64,
+    owners: Vec<Pubkey>,
+    threshold: u8,
+    min_delay: i64,
+    weights: Vec<u16>,
+    name: [u8; 32],
+    default_tx_ttl: i64,
+    allow_self_calls: bool,
+    legacy_nonces: bool,
+    max_data_size: u16,
+    min_owners: u8,
+    id_seed: Option<String>,
+)]
+pub struct Initialize<'info> {
+    // Declared before the multisig so the seeds below can use its key
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    #[account(
+        init, 
+        payer = creator, 
+        space = Multisig::space(std::cmp::min(owners.len() + OWNER_HEADROOM, MAX_OWNERS)),
+        seeds = [
+            b"multisig",
+            &resolve_multisig_id(multisig_id, &creator.key(), id_seed.as_deref()).to_le_bytes(),
+        ],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct CreateTransaction<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        // Evaluated before the account is created, see rent_funded_space
+        space = rent_funded_space(&proposer, Transaction::space(multisig.max_data_size as usize))?,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    /// CHECK: Optional system nonce account, deserialized and validated in the handler
+    #[account(mut)]
+    pub nonce_account: Option<AccountInfo<'info>>,
+
+    /// CHECK: Sysvar required by nonce account (optional)
+    #[allow(deprecated)]
+    pub recent_blockhashes: Option<Sysvar<'info, RecentBlockhashes>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct CreateAndApprove<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        // Evaluated before the account is created, see rent_funded_space
+        space = rent_funded_space(&proposer, Transaction::space(multisig.max_data_size as usize))?,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct ProposeSolTransfer<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        // Evaluated before the account is created, see rent_funded_space
+        space = rent_funded_space(&proposer, Transaction::space(multisig.max_data_size as usize))?,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    // Holds the multisig's SOL; anyone can deposit by transferring to this address
+    #[account(
+        seeds = [b"vault", multisig.key().as_ref()],
+        bump,
+    )]
+    pub vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct ProposeTokenTransfer<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        // Evaluated before the account is created, see rent_funded_space
+        space = rent_funded_space(&proposer, Transaction::space(multisig.max_data_size as usize))?,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    /// CHECK: SPL token mint, decimals are read in the handler
+    #[account(owner = TOKEN_PROGRAM_ID)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Token account owned by the multisig PDA, validated in the handler
+    #[account(owner = TOKEN_PROGRAM_ID)]
+    pub source: UncheckedAccount<'info>,
+
+    /// CHECK: Recipient token account, validated by the token program at execution
+    #[account(owner = TOKEN_PROGRAM_ID)]
+    pub destination: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct ApproveTransaction<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct CreateTransactionsBatch<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    pub system_program: Program<'info, System>,
+    // Transaction PDAs to create are passed as writable remaining_accounts
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct ApproveMany<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+    // Transaction PDAs to approve are passed as remaining_accounts
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct RevokeApproval<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct ConfirmLargeOutflow<'info> {
+    pub guardian: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct RejectTransaction<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+}
+
+// Fix: Remove the problematic remaining_accounts field from the struct
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct ExecuteTransaction<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    // Signs vault transfers and funds the executor reward
+    #[account(
+        mut,
+        seeds = [b"vault", multisig.key().as_ref()],
+        bump,
+    )]
+    pub vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    // remaining_accounts are accessed via ctx.remaining_accounts in the function
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct CreateTransactionIdempotent<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    // Existing accounts are checked against the request in the handler
+    #[account(
+        init_if_needed,
+        payer = proposer,
+        // Evaluated before the account is created, see idempotent_space
+        space = idempotent_space(&proposer, transaction, Transaction::space(multisig.max_data_size as usize))?,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct ExecuteAndClose<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", multisig.key().as_ref()],
+        bump,
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// CHECK: Rent refund destination, must be the original proposer
+    #[account(mut, address = transaction.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct ExecuteWithSignatures<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", multisig.key().as_ref()],
+        bump,
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// CHECK: address is constrained to the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct MultisigView<'info> {
+    #[account(
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+}
+
+#[derive(Accounts)]
+pub struct DeriveAddress {}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct CreateNonceAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    /// CHECK: Created and initialized as a system nonce account in the handler
+    #[account(
+        mut,
+        seeds = [b"nonce", multisig.key().as_ref()],
+        bump
+    )]
+    pub nonce_account: UncheckedAccount<'info>,
+
+    #[allow(deprecated)]
+    pub recent_blockhashes: Sysvar<'info, RecentBlockhashes>,
+
+    pub rent: Sysvar<'info, Rent>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct PingMultisig<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct GuardianPause<'info> {
+    pub guardian: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct SetDelegate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct TransactionView<'info> {
+    #[account(
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct CancelTransaction<'info> {
+    pub canceller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    /// CHECK: Rent refund destination, must be the original proposer
+    #[account(mut, address = transaction.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct ReleaseNonce<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    /// CHECK: May already be closed; deserialized in the handler when it isn't
+    #[account(
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct FastTransfer<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", multisig.key().as_ref()],
+        bump,
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// CHECK: Any account can receive SOL, checked against the recipient argument
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", multisig.key().as_ref()],
+        bump,
+    )]
+    pub vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct RegisterToken<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    /// CHECK: SPL token mint, validated by the associated token program
+    #[account(owner = TOKEN_PROGRAM_ID)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: The multisig's associated token account for mint, checked in the handler
+    #[account(mut)]
+    pub token_account: UncheckedAccount<'info>,
+
+    /// CHECK: SPL token program
+    #[account(address = TOKEN_PROGRAM_ID)]
+    pub token_program: UncheckedAccount<'info>,
+
+    /// CHECK: Associated token account program
+    #[account(address = ASSOCIATED_TOKEN_PROGRAM_ID)]
+    pub associated_token_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct EmergencyWithdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", multisig.key().as_ref()],
+        bump,
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// CHECK: Any account can receive SOL, checked against the recovery argument
+    #[account(mut)]
+    pub recovery: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    // Every owner must be passed as a signer in remaining_accounts
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64, nonce: u64)]
+pub struct CloseTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        close = proposer,
+        constraint = transaction.did_execute @ ErrorCode::NotExecuted,
+        constraint = !multisig.pinned_nonces.contains(&transaction.nonce) @ ErrorCode::TransactionPinned,
+        seeds = [b"transaction", multisig.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    /// CHECK: Rent refund destination, must be the original proposer
+    #[account(mut, address = transaction.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct SetPaused<'info> {
+    #[account(
+        constraint = authority.key() == multisig.creator @ ErrorCode::NotCreator
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct CreatorAuth<'info> {
+    #[account(
+        constraint = authority.key() == multisig.creator @ ErrorCode::NotCreator
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct AddOwner<'info> {
+    #[account(
+        mut,
+        signer,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    // Pays for the extra owner slot if the account has to grow
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct AcceptOwnership<'info> {
+    // The nominee, also paying for the extra owner slot if one is needed
+    #[account(mut)]
+    pub candidate: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct CloseMultisig<'info> {
+    #[account(
+        mut,
+        signer,
+        close = creator,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        seeds = [b"vault", multisig.key().as_ref()],
+        bump,
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// CHECK: Receives the rent, must be the multisig's creator
+    #[account(mut, address = multisig.creator)]
+    pub creator: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct ApplyGovernanceChange<'info> {
+    // Pays for the extra owner slot if an added owner needs one
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct VetoGovernanceChange<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct Migrate<'info> {
+    // Pays for the extra space of the new layout
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: May be in an old layout that Account<Multisig> can't read; the
+    /// discriminator is checked in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump,
+    )]
+    pub multisig: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct Recover<'info> {
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        init,
+        payer = guardian,
+        space = RECOVERY_SPACE,
+        seeds = [b"recovery", multisig.key().as_ref()],
+        bump
+    )]
+    pub recovery: Account<'info, Recovery>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct ApproveRecovery<'info> {
+    pub guardian: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        seeds = [b"recovery", multisig.key().as_ref()],
+        bump,
+    )]
+    pub recovery: Account<'info, Recovery>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct FinalizeRecovery<'info> {
+    // Pays for the extra owner slots if the account has to grow
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"recovery", multisig.key().as_ref()],
+        bump,
+    )]
+    pub recovery: Account<'info, Recovery>,
+
+    /// CHECK: Rent refund destination, must be the guardian who opened the recovery
+    #[account(mut, address = recovery.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct CancelRecovery<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"recovery", multisig.key().as_ref()],
+        bump,
+    )]
+    pub recovery: Account<'info, Recovery>,
+
+    /// CHECK: Rent refund destination, must be the guardian who opened the recovery
+    #[account(mut, address = recovery.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+// The multisig PDA must sign, so these instructions have to be approved
+// by the owners and run via execute_transaction
+#[derive(Accounts)]
+#[instruction(multisig_id: u64)]
+pub struct MultisigAuth<'info> {
+    #[account(
+        mut,
+        signer,
+        seeds = [b"multisig", &multisig_id.to_le_bytes()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, Multisig>,
+}
+
+#[account]
+pub struct Multisig {
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+    // In Percentage mode threshold is ignored in favour of threshold_bps
+    pub threshold_mode: ThresholdMode,
+    pub threshold_bps: u16,
+    pub creator: Pubkey,
+    pub multisig_id: u64,
+    // Only used in legacy_nonces mode
+    pub used_nonces: Vec<u64>,
+    // Nonce the next proposal must use unless legacy_nonces is set
+    pub seq: u64,
+    // Accept arbitrary caller-chosen nonces tracked in used_nonces instead of seq
+    pub legacy_nonces: bool,
+    // Seconds that must pass between reaching threshold and execution
+    pub min_delay: i64,
+    // Seconds that must pass between two approvals of the same transaction
+    pub min_vote_spacing: i64,
+    // Parallel to owners; when empty every owner has a weight of 1 and
+    // threshold is a plain approval count, otherwise it is a required weight sum
+    pub weights: Vec<u16>,
+    // UTF-8, zero padded
+    pub name: [u8; 32],
+    // Lifetime given to proposals created with expires_at = 0, 0 = never expire
+    pub default_tx_ttl: i64,
+    // Whether proposals may invoke this program, i.e. the MultisigAuth admin
+    // instructions. Off unless the creator opts in.
+    pub allow_self_calls: bool,
+    pub paused: bool,
+    // Set for the duration of execute_transaction's CPIs
+    pub executing: bool,
+    // Lamports owners may move per UTC day through fast_transfer, 0 disables it
+    pub daily_limit: u64,
+    pub spent_today: u64,
+    pub last_reset_ts: i64,
+    pub created_at: i64,
+    // Updated on every create, approve and execute
+    pub last_activity: i64,
+    // Can replace the owner set through recover after RECOVERY_DELAY
+    pub guardians: Vec<Pubkey>,
+    pub guardian_threshold: u8,
+    // Proposals created but not yet executed or cancelled
+    pub open_tx_count: u16,
+    // Auto-pause when more than max_tx_per_window proposals are created within
+    // window_secs, 0 disables it
+    pub max_tx_per_window: u16,
+    pub window_secs: i64,
+    // Creation times inside the current window, oldest first
+    pub recent_creations: Vec<i64>,
+    // Layout version, see migrate. New fields must be appended after this one
+    // so older, shorter accounts can be read by zero-padding them.
+    pub version: u8,
+    // Programs proposals may target, empty = unrestricted
+    pub allowed_programs: Vec<Pubkey>,
+    // Destinations propose_sol_transfer, propose_token_transfer and
+    // fast_transfer may pay, empty = unrestricted
+    pub allowed_recipients: Vec<Pubkey>,
+    // Stamped into every event this multisig emits, strictly increasing
+    pub event_seq: u64,
+    // Nonces of open proposals, bounded by MAX_OPEN_TRANSACTIONS. Proposals
+    // created before the migration that added it are not listed.
+    pub open_nonces: Vec<u64>,
+    // Per-instruction data limit, sizes this multisig's Transaction accounts
+    pub max_data_size: u16,
+    // Seconds membership and threshold changes wait in pending_governance_change
+    // before apply_governance_change can run them, 0 applies them immediately
+    pub min_governance_delay: i64,
+    pub pending_governance_change: Option<GovernanceChange>,
+    // Owner count remove_owner and recovery may not go below
+    pub min_owners: u8,
+    // Mints whose associated token accounts were created by register_token
+    pub tracked_mints: Vec<Pubkey>,
+    // Reject any account repeated within one proposed instruction instead of
+    // only repeats with conflicting signer/writable flags
+    pub reject_duplicate_accounts: bool,
+    // Canonical PDA bump, so account validation doesn't re-derive it
+    pub bump: u8,
+    // Keep a transaction's eta when a revocation drops it below the threshold
+    pub persist_timelock_on_revoke: bool,
+    // Hot keys allowed to approve for an owner, at most one per owner
+    pub delegates: Vec<Delegation>,
+    // Keep executed proposals' instructions instead of clearing them
+    pub retain_executed_data: bool,
+    // Empty, or parallel to owners like weights. A replaced owner's successor
+    // inherits the role.
+    pub roles: Vec<[u8; 16]>,
+    // Per-role approver minimums checked at execution, see set_required_roles
+    pub required_roles: Vec<RoleRequirement>,
+    // Outstanding guardian votes for guardian_set_paused, one per guardian
+    pub pause_votes: Vec<PauseVote>,
+    // Weight needed for proposals without writable accounts, capped at the
+    // regular requirement. 0 = same as the regular threshold.
+    pub readonly_threshold: u8,
+    // Lamports a single proposal may move through system transfers, 0 = no cap
+    pub max_tx_value: u64,
+    // Owners whose rejection alone makes a proposal unexecutable
+    pub veto_owners: Vec<Pubkey>,
+    // Bumped whenever owner positions shift or a key is swapped, which
+    // invalidates the approval bitmaps of pending transactions
+    pub owner_set_seq: u64,
+    // Approvals cast per owner, parallel to owners; empty while tracking is off
+    pub approval_counts: Vec<u64>,
+    // Restricts execution to owners instead of anyone, e.g. relayers
+    pub executor_must_be_owner: bool,
+    // Seconds past a transaction's expires_at during which it can still execute
+    pub expiry_grace: i64,
+    // Candidate waiting to accept_ownership, see nominate_owner
+    pub pending_nomination: Option<OwnerNomination>,
+    // Share of the vault balance, in basis points, above which an execution's
+    // SOL transfers need a guardian's confirmation. 0 = off
+    pub large_outflow_bps: u16,
+    // Large outflow that paused the multisig, until a guardian confirms it
+    pub pending_large_tx: Option<LargeOutflowHold>,
+    // Confirmed large outflow, cleared when it executes or is cancelled
+    pub released_large_tx: Option<LargeOutflowRelease>,
+    // depends_on of every open proposal, one entry per dependent. These
+    // transactions can't be closed while a dependent still needs them.
+    pub pinned_nonces: Vec<u64>,
+    // Proposal whose CPIs are running, only meaningful while executing is set
+    pub executing_nonce: u64,
+    // Required weight sum in Absolute mode when non-zero, replacing threshold
+    // so weighted multisigs aren't capped at 255
+    pub weight_threshold: u64,
+}
+
+// A pending owner-set replacement, one per multisig
+#[account]
+pub struct Recovery {
+    pub multisig: Pubkey,
+    // Guardian who opened it, refunded on close
+    pub proposer: Pubkey,
+    pub new_owners: Vec<Pubkey>,
+    pub new_threshold: u8,
+    pub approvals: Vec<Pubkey>,
+    // Earliest finalization time, 0 until the guardian threshold is reached
+    pub eta: i64,
+}
+
+impl Multisig {
+    // Account size needed to hold `owner_capacity` owners
+    pub const fn space(owner_capacity: usize) -> usize {
+        8 +                                   // discriminator
+        4 + (32 * owner_capacity) +           // owners vec
+        1 +                                   // threshold
+        1 +                                   // threshold_mode
+        2 +                                   // threshold_bps
+        32 +                                  // creator
+        8 +                                   // multisig_id
+        4 + (8 * MAX_STORED_NONCES) +         // used_nonces vec
+        8 +                                   // seq
+        1 +                                   // legacy_nonces
+        8 +                                   // min_delay
+        8 +                                   // min_vote_spacing
+        4 + (2 * owner_capacity) +            // weights vec
+        32 +                                  // name
+        8 +                                   // default_tx_ttl
+        1 +                                   // allow_self_calls
+        1 +                                   // paused
+        1 +                                   // executing
+        8 +                                   // daily_limit
+        8 +                                   // spent_today
+        8 +                                   // last_reset_ts
+        8 +                                   // created_at
+        8 +                                   // last_activity
+        4 + (32 * MAX_GUARDIANS) +            // guardians vec
+        1 +                                   // guardian_threshold
+        2 +                                   // open_tx_count
+        2 +                                   // max_tx_per_window
+        8 +                                   // window_secs
+        4 + (8 * (MAX_RATE_LIMIT + 1)) +      // recent_creations vec
+        1 +                                   // version
+        4 + (32 * MAX_ALLOWED_PROGRAMS) +     // allowed_programs vec
+        4 + (32 * MAX_ALLOWED_RECIPIENTS) +   // allowed_recipients vec
+        8 +                                   // event_seq
+        4 + (8 * MAX_OPEN_TRANSACTIONS as usize) + // open_nonces vec
+        2 +                                   // max_data_size
+        8 +                                   // min_governance_delay
+        1 + GovernanceChange::SPACE +         // pending_governance_change option
+        1 +                                   // min_owners
+        4 + (32 * MAX_TRACKED_MINTS) +        // tracked_mints vec
+        1 +                                   // reject_duplicate_accounts
+        1 +                                   // bump
+        1 +                                   // persist_timelock_on_revoke
+        4 + (64 * MAX_DELEGATES) +            // delegates vec
+        1 +                                   // retain_executed_data
+        4 + (16 * owner_capacity) +           // roles vec
+        4 + (17 * MAX_REQUIRED_ROLES) +       // required_roles vec
+        4 + (33 * MAX_GUARDIANS) +            // pause_votes vec
+        1 +                                   // readonly_threshold
+        8 +                                   // max_tx_value
+        4 + (32 * MAX_VETO_OWNERS) +          // veto_owners vec
+        8 +                                   // owner_set_seq
+        4 + (8 * owner_capacity) +            // approval_counts vec
+        1 +                                   // executor_must_be_owner
+        8 +                                   // expiry_grace
+        1 + 32 + 8 +                          // pending_nomination option
+        2 +                                   // large_outflow_bps
+        1 + 8 + 8 +                           // pending_large_tx option
+        1 + 8 + 32 +                          // released_large_tx option
+        4 + (8 * MAX_OPEN_TRANSACTIONS as usize) + // pinned_nonces vec
+        8 +                                   // executing_nonce
+        8                                     // weight_threshold
+    }
+
+    // Sequence number for the next event, giving indexers a total order
+    // even between events in the same slot
+    pub fn next_event_seq(&mut self) -> u64 {
+        self.event_seq += 1;
+        self.event_seq
+    }
+
+    pub fn owner_for_delegate(&self, delegate: &Pubkey) -> Option<Pubkey> {
+        self.delegates
+            .iter()
+            .find(|delegation| delegation.delegate == *delegate)
+            .map(|delegation| delegation.owner)
+    }
+
+    // True when the allowlist is off or any of the candidates is on it
+    pub fn recipient_allowed(&self, candidates: &[Pubkey]) -> bool {
+        self.allowed_recipients.is_empty()
+            || candidates.iter().any(|candidate| self.allowed_recipients.contains(candidate))
+    }
+
+    // Whether the transaction's SOL outflow trips the large outflow circuit
+    // breaker against the current vault balance
+    pub fn outflow_needs_confirmation(&self, transaction: &Transaction, vault_balance: u64) -> bool {
+        self.large_outflow_bps > 0
+            && !self
+                .released_large_tx
+                .is_some_and(|release| release.nonce == transaction.nonce && release.tx_hash == transaction.tx_hash)
+            && transaction.sol_outflow() as u128 * BPS_DENOMINATOR as u128
+                > vault_balance as u128 * self.large_outflow_bps as u128
+    }
+
+    pub fn owner_index(&self, owner: &Pubkey) -> Option<usize> {
+        self.owners.iter().position(|o| o == owner)
+    }
+
+    pub fn weight_of(&self, owner: &Pubkey) -> u64 {
+        match self.owner_index(owner) {
+            Some(index) => self.weights.get(index).copied().unwrap_or(1) as u64,
+            None => 0,
+        }
+    }
+
+    // Only current owners count towards the threshold
+    pub fn approval_weight(&self, approvals: &[Pubkey]) -> u64 {
+        approvals.iter().map(|approver| self.weight_of(approver)).sum()
+    }
+
+    pub fn total_weight(&self) -> u64 {
+        self.approval_weight(&self.owners)
+    }
+
+    // Weight needed to execute under the current threshold mode
+    pub fn required_weight(&self) -> u64 {
+        match self.threshold_mode {
+            ThresholdMode::Absolute if self.weight_threshold > 0 => self.weight_threshold,
+            ThresholdMode::Absolute => self.threshold as u64,
+            ThresholdMode::Percentage => {
+                let denominator = BPS_DENOMINATOR as u64;
+                (self.total_weight() * self.threshold_bps as u64).div_ceil(denominator)
+            }
+        }
+    }
+
+    pub fn threshold_met(&self, approvals: &[Pubkey]) -> bool {
+        self.approval_weight(approvals) >= self.required_weight()
+    }
+
+    // Read-only proposals can run under the lower readonly_threshold, if set.
+    // The proposer's required_approvals can only raise the bar.
+    pub fn required_weight_for(&self, transaction: &Transaction) -> u64 {
+        let required = self.required_weight();
+        let required = if transaction.read_only && self.readonly_threshold > 0 {
+            required.min(self.readonly_threshold as u64)
+        } else {
+            required
+        };
+        required.max(transaction.required_approvals as u64)
+    }
+
+    pub fn threshold_met_for(&self, transaction: &Transaction, approvals: &[Pubkey]) -> bool {
+        self.approval_weight(approvals) >= self.required_weight_for(transaction)
+    }
+
+    // Each required role is held by enough of the approvers
+    pub fn role_quorum_met(&self, approvals: &[Pubkey]) -> bool {
+        self.required_roles.iter().all(|requirement| {
+            let holders = approvals
+                .iter()
+                .filter(|approver| {
+                    self.owners
+                        .iter()
+                        .position(|owner| owner == *approver)
+                        .and_then(|index| self.roles.get(index))
+                        .is_some_and(|role| *role == requirement.role)
+                })
+                .count();
+            holders >= requirement.count as usize
+        })
+    }
+
+    // Whether the owners hold enough of each required role for role_quorum_met
+    // to pass at all, which is all set_required_roles can be reached through
+    pub fn role_quorum_reachable(&self) -> bool {
+        self.role_quorum_met(&self.owners)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ThresholdMode {
+    Absolute,
+    Percentage,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Approval {
+    pub owner: Pubkey,
+    pub approved_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PauseVote {
+    pub guardian: Pubkey,
+    pub paused: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RoleRequirement {
+    pub role: [u8; 16],
+    pub count: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Delegation {
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ApprovalMemo {
+    pub owner: Pubkey,
+    pub memo: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactionAccount {
+    // The account itself, or the lookup table holding it when lookup_index is set
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub lookup_index: Option<u8>,
+}
+
+// How a stored account is addressed, see TransactionAccount::reference
+pub enum TransactionAccountRef {
+    Key(Pubkey),
+    // Entry `index` of an address lookup table the executor passes along
+    Lookup { table: Pubkey, index: u8 },
+}
+
+impl TransactionAccount {
+    pub fn reference(&self) -> TransactionAccountRef {
+        match self.lookup_index {
+            None => TransactionAccountRef::Key(self.pubkey),
+            Some(index) => TransactionAccountRef::Lookup { table: self.pubkey, index },
+        }
+    }
+}
+
+// One entry of create_transactions_batch
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BatchProposal {
+    pub nonce: u64,
+    pub program_id: Pubkey,
+    pub accounts: Vec<TransactionAccount>,
+    pub data: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactionInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<TransactionAccount>,
+    pub data: Vec<u8>,
+}
+
+impl From<anchor_lang::solana_program::instruction::Instruction> for TransactionInstruction {
+    fn from(ix: anchor_lang::solana_program::instruction::Instruction) -> Self {
+        TransactionInstruction {
+            program_id: ix.program_id,
+            accounts: ix.accounts.into_iter().map(|meta| TransactionAccount {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+                lookup_index: None,
+            }).collect(),
+            data: ix.data,
+        }
+    }
+}
+
+#[account]
+pub struct Transaction {
+    pub multisig: Pubkey,
+    pub proposer: Pubkey,
+    // Approvals of transactions created before approval_bitmap existed. Always
+    // empty for new ones; moved into the bitmap on the next approval change.
+    pub approvals: Vec<Approval>,
+    pub rejections: Vec<Pubkey>,
+    // Set once rejections make the threshold unreachable
+    pub rejected: bool,
+    // Instructions already run by chunked execution
+    pub executed_count: u8,
+    pub did_execute: bool,
+    pub nonce: u64,
+    pub instructions: Vec<TransactionInstruction>,
+    pub expires_at: i64,
+    // Timestamp at which the threshold was reached, 0 until then
+    pub eta: i64,
+    // SHA-256 of the instructions and terms, see hash_proposal
+    pub tx_hash: [u8; 32],
+    // Lamports paid from the vault to whoever executes, 0 for none
+    pub executor_reward: u64,
+    // Most recent approver memos, e.g. a reference to an offline signing session
+    pub memos: Vec<ApprovalMemo>,
+    // Nonce of a proposal that must have executed before this one can
+    pub depends_on: Option<u64>,
+    // How urgently the proposer wants it executed, for relayers choosing a fee
+    pub priority_hint: PriorityHint,
+    // Free-form text from the proposer, untrusted; empty when none was given
+    pub description: String,
+    // No instruction has a writable account, see Multisig::readonly_threshold
+    pub read_only: bool,
+    // Inclusive slot range execution is allowed in, 0 leaves that end open
+    pub valid_from_slot: u64,
+    pub valid_until_slot: u64,
+    // Rejected by a veto owner; rejected is set as well
+    pub vetoed: bool,
+    // Approval weight the proposer asked for on top of the multisig's, 0 = none
+    pub required_approvals: u8,
+    // Bit i is set when owners[i] approved, see Multisig::owner_set_seq
+    pub approval_bitmap: u64,
+    // Multisig::owner_set_seq the bitmap was recorded against
+    pub owner_set_seq: u64,
+    // approval_times[i] is when owners[i] approved, 0 for no approval
+    pub approval_times: Vec<i64>,
+    // Addresses the lookup table references resolved to at proposal time, see
+    // hash_lookup_keys. Zero when there are none
+    pub lookup_keys_hash: [u8; 32],
+}
+
+// Accounts created through init are limited to what a CPI may allocate
+const _: () = assert!(
+    Transaction::space(MAX_INSTRUCTION_DATA_SIZE)
+        <= anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE
+);
+const _: () = assert!(
+    Multisig::space(MAX_OWNERS) <= anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE
+);
+
+impl Transaction {
+    // Account size for a multisig whose instructions carry at most
+    // `max_data_size` bytes of data each
+    pub const fn space(max_data_size: usize) -> usize {
+        8 +                                   // discriminator
+        32 +                                  // multisig
+        32 +                                  // proposer
+        4 +                                   // approvals vec (legacy, empty)
+        4 + (32 * MAX_OWNERS) +               // rejections vec
+        1 +                                   // rejected
+        1 +                                   // executed_count
+        1 +                                   // did_execute
+        8 +                                   // nonce
+        4 + MAX_INSTRUCTIONS * (              // instructions vec
+            32 +                                  // program_id
+            4 + (36 * MAX_INSTRUCTION_ACCOUNTS) + // accounts vec (36 bytes each)
+            4 + max_data_size                     // data vec
+        ) +
+        8 +                                   // expires_at
+        8 +                                   // eta
+        32 +                                  // tx_hash
+        8 +                                   // executor_reward
+        4 + (64 * MAX_APPROVAL_MEMOS) +       // memos vec (owner + memo)
+        1 + 8 +                               // depends_on option
+        1 +                                   // priority_hint
+        4 + MAX_DESCRIPTION_LEN +             // description string
+        1 +                                   // read_only
+        8 +                                   // valid_from_slot
+        8 +                                   // valid_until_slot
+        1 +                                   // vetoed
+        1 +                                   // required_approvals
+        8 +                                   // approval_bitmap
+        8 +                                   // owner_set_seq
+        4 + (8 * MAX_OWNERS) +                // approval_times vec
+        32                                    // lookup_keys_hash
+    }
+
+    // Lamports the proposal moves: its system transfers plus the executor reward
+    pub fn sol_outflow(&self) -> u64 {
+        sol_transfer_total(&self.instructions).saturating_add(self.executor_reward)
+    }
+
+    // Approvals recorded before the owner set last changed no longer count.
+    // Legacy approvals are keyed by owner, so they count for whoever is still an owner.
+    pub fn approvers(&self, multisig: &Multisig) -> Vec<Pubkey> {
+        if !self.approvals.is_empty() {
+            return self
+                .approvals
+                .iter()
+                .map(|approval| approval.owner)
+                .filter(|owner| multisig.owners.contains(owner))
+                .collect();
+        }
+        if self.owner_set_seq != multisig.owner_set_seq {
+            return Vec::new();
+        }
+        multisig
+            .owners
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.approval_bitmap & (1 << index) != 0)
+            .map(|(_, owner)| *owner)
+            .collect()
+    }
+
+    pub fn approvals_count(&self, multisig: &Multisig) -> u8 {
+        self.approvers(multisig).len() as u8
+    }
+
+    pub fn set_approval(&mut self, multisig: &Multisig, owner: &Pubkey, approved_at: i64) {
+        self.sync_approvals(multisig);
+        if let Some(index) = multisig.owner_index(owner) {
+            self.mark_approval(index, approved_at);
+        }
+    }
+
+    pub fn clear_approval(&mut self, multisig: &Multisig, owner: &Pubkey) {
+        self.sync_approvals(multisig);
+        if let Some(index) = multisig.owner_index(owner) {
+            self.approval_bitmap &= !(1 << index);
+            if let Some(approved_at) = self.approval_times.get_mut(index) {
+                *approved_at = 0;
+            }
+        }
+    }
+
+    // Brings the bitmap in line with multisig's current owner set: legacy
+    // approvals are moved into it, and stale bits are dropped
+    fn sync_approvals(&mut self, multisig: &Multisig) {
+        if self.approvals.is_empty() && self.owner_set_seq == multisig.owner_set_seq {
+            return;
+        }
+        let legacy = std::mem::take(&mut self.approvals);
+        self.approval_bitmap = 0;
+        self.approval_times = Vec::new();
+        self.owner_set_seq = multisig.owner_set_seq;
+        for approval in legacy {
+            if let Some(index) = multisig.owner_index(&approval.owner) {
+                self.mark_approval(index, approval.approved_at);
+            }
+        }
+    }
+
+    fn mark_approval(&mut self, index: usize, approved_at: i64) {
+        self.approval_bitmap |= 1 << index;
+        if self.approval_times.len() <= index {
+            self.approval_times.resize(index + 1, 0);
+        }
+        self.approval_times[index] = approved_at;
+    }
+
+    pub fn within_slot_window(&self, slot: u64) -> bool {
+        (self.valid_from_slot == 0 || slot >= self.valid_from_slot)
+            && (self.valid_until_slot == 0 || slot <= self.valid_until_slot)
+    }
+
+    pub fn has_approved(&self, multisig: &Multisig, owner: &Pubkey) -> bool {
+        if !self.approvals.is_empty() {
+            return self.approvals.iter().any(|approval| approval.owner == *owner);
+        }
+        self.owner_set_seq == multisig.owner_set_seq
+            && multisig
+                .owner_index(owner)
+                .is_some_and(|index| self.approval_bitmap & (1 << index) != 0)
+    }
+
+    // Whether an approval at `now` would follow the previous one by less than `spacing`
+    pub fn vote_too_soon(&self, spacing: i64, now: i64) -> bool {
+        let last = self
+            .approvals
+            .iter()
+            .map(|approval| approval.approved_at)
+            .chain(self.approval_times.iter().copied())
+            .max()
+            .unwrap_or(0);
+        spacing > 0 && last != 0 && now < last + spacing
+    }
+
+    // An expires_at of 0 means the transaction never expires
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now > self.expires_at
+    }
+
+    // Execution may still happen for `grace` seconds after expires_at
+    pub fn execution_expired(&self, grace: i64, now: i64) -> bool {
+        self.expires_at != 0 && now > self.expires_at.saturating_add(grace)
+    }
+
+    pub fn timelock_elapsed(&self, min_delay: i64, now: i64) -> bool {
+        min_delay <= 0 || (self.eta != 0 && now >= self.eta + min_delay)
+    }
+}
+
+// An owner's off-chain signature over approval_message
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Ed25519Approval {
+    pub owner: Pubkey,
+    pub signature: [u8; 64],
+}
+
+// Returned by transaction_state
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactionState {
+    pub approvals_count: u8,
+    // Approval weight this proposal needs, after threshold mode, read-only
+    // threshold and any per-proposal override
+    pub threshold: u64,
+    pub did_execute: bool,
+    pub is_expired: bool,
+    pub executable: bool,
+}
+
+// Returned by create_transaction so CPI callers don't have to re-derive the PDA
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreatedTransaction {
+    pub transaction: Pubkey,
+    pub bump: u8,
+}
+
+// Returned by the execute instructions. Held and Queued mean nothing ran: the
+// large outflow circuit breaker paused the multisig until a guardian confirms,
+// or execute_with_signatures started the timelock.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionOutcome {
+    Executed,
+    ChunkExecuted,
+    Held,
+    Queued,
+}
+
+// Outcome of simulate_transaction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationCode {
+    Ok,
+    AlreadyExecuted,
+    // Program account not in remaining_accounts
+    MissingProgram,
+    ProgramNotExecutable,
+    // Instruction account not in remaining_accounts
+    MissingAccount,
+    // Supplied account lacks the approved signer/writable flags
+    PrivilegeMismatch,
+    // Read-only account that doesn't exist on-chain
+    AccountNotFound,
+    UnexpectedOwner,
+    // Lookup table missing from remaining_accounts or entry out of range
+    InvalidLookupTable,
+}
+
+// Returned by simulate_transaction; instruction_index and account point at the
+// first problem and are zeroed when the code is Ok
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SimulationResult {
+    pub code: SimulationCode,
+    pub instruction_index: u8,
+    pub account: Pubkey,
+}
+
+impl SimulationResult {
+    fn new(code: SimulationCode, instruction_index: u8, account: Pubkey) -> Self {
+        Self { code, instruction_index, account }
+    }
+}
+
+// Returned by multisig_summary
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MultisigSummary {
+    pub owner_count: u8,
+    pub threshold: u8,
+    pub used_nonce_count: u16,
+    pub paused: bool,
+    pub created_at: i64,
+    // Parallel to the multisig's owners, empty if no role was ever set
+    pub roles: Vec<[u8; 16]>,
+}
+
+// One entry per tracked mint, returned by token_balances
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TokenBalance {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub amount: u64,
+}
+
+// Returned by health_check. Weights are approval counts when weighted voting is off.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct HealthReport {
+    pub live: bool,
+    pub required_weight: u64,
+    pub total_weight: u64,
+    pub role_quorum_reachable: bool,
+}
+
+// Returned by derive_transaction_address
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactionAddress {
+    pub address: Pubkey,
+    pub bump: u8,
+}
+
+// Returned by is_owner
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OwnerStatus {
+    pub is_owner: bool,
+    // Vote weight, only set for owners of a weighted multisig
+    pub weight: Option<u16>,
+}
+
+#[event]
+pub struct MultisigInitialized {
+    pub multisig: Pubkey,
+    pub creator: Pubkey,
+    pub multisig_id: u64,
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+    pub name: [u8; 32],
+    pub created_at: i64,
+    pub last_activity: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct MultisigRenamed {
+    pub multisig: Pubkey,
+    pub old_name: [u8; 32],
+    pub new_name: [u8; 32],
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct TransactionCreated {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub proposer: Pubkey,
+    pub nonce: u64,
+    pub tx_hash: [u8; 32],
+    // Effective expiry after applying default_tx_ttl, 0 = never
+    pub expires_at: i64,
+    // Proposer-supplied, treat as untrusted text
+    pub description: String,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct SolTransferProposed {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub vault: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct TokenTransferProposed {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub mint: Pubkey,
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct TransactionApproved {
+    pub transaction: Pubkey,
+    pub approver: Pubkey,
+    pub approvals_count: u8,
+    pub threshold: u8,
+    pub approved_at: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ThresholdReached {
+    pub transaction: Pubkey,
+    pub nonce: u64,
+    pub approvals_count: u8,
+    pub event_seq: u64,
+}
+
+// The timelock was cleared because a revocation dropped the transaction below
+// the threshold
+#[event]
+pub struct TransactionDequeued {
+    pub transaction: Pubkey,
+    pub approvals_count: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct TransactionQueued {
+    pub transaction: Pubkey,
+    pub eta: i64,
+    pub executable_at: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ApprovalRevoked {
+    pub transaction: Pubkey,
+    pub owner: Pubkey,
+    pub approvals_count: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct TransactionRejectionRecorded {
+    pub transaction: Pubkey,
+    pub owner: Pubkey,
+    pub rejections_count: u8,
+    pub rejected: bool,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct TransactionExecuted {
+    pub transaction: Pubkey,
+    pub executor: Pubkey,
+    pub reward_paid: u64,
+    pub priority_hint: PriorityHint,
+    pub tx_hash: [u8; 32],
+    pub event_seq: u64,
+}
+
+// Emitted for every chunk that doesn't complete the transaction
+#[event]
+pub struct TransactionChunkExecuted {
+    pub transaction: Pubkey,
+    pub executor: Pubkey,
+    pub start_index: u8,
+    pub count: u8,
+    pub remaining: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct TransactionCancelled {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub nonce: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct NonceAccountCreated {
+    pub multisig: Pubkey,
+    pub nonce_account: Pubkey,
+    pub payer: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct NonceReleased {
+    pub multisig: Pubkey,
+    pub nonce: u64,
+    pub released_by: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct Deposited {
+    pub multisig: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub new_balance: u64,
+    pub memo: [u8; 32],
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct EmergencyWithdrawn {
+    pub multisig: Pubkey,
+    pub recovery: Pubkey,
+    pub amount: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct FastTransferExecuted {
+    pub multisig: Pubkey,
+    pub owner: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub spent_today: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct MinVoteSpacingChanged {
+    pub multisig: Pubkey,
+    pub old_spacing: i64,
+    pub new_spacing: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct RateLimitChanged {
+    pub multisig: Pubkey,
+    pub max_tx_per_window: u16,
+    pub window_secs: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct AutoPaused {
+    pub multisig: Pubkey,
+    // Proposals seen in the window, including the one that tripped it
+    pub count: u16,
+    pub window_secs: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct DailyLimitChanged {
+    pub multisig: Pubkey,
+    pub old_limit: u64,
+    pub new_limit: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct TransactionClosed {
+    pub transaction: Pubkey,
+    pub proposer: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct PauseStateChanged {
+    pub multisig: Pubkey,
+    pub paused: bool,
+    pub authority: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct CreatorTransferred {
+    pub multisig: Pubkey,
+    pub old_creator: Pubkey,
+    pub new_creator: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct SelfCallsToggled {
+    pub multisig: Pubkey,
+    pub allowed: bool,
+    pub authority: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ThresholdChanged {
+    pub multisig: Pubkey,
+    pub old_threshold: u8,
+    pub new_threshold: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct WeightThresholdChanged {
+    pub multisig: Pubkey,
+    pub old_weight_threshold: u64,
+    pub weight_threshold: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ThresholdBpsChanged {
+    pub multisig: Pubkey,
+    pub threshold_bps: u16,
+    pub required_weight: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct OwnerNominated {
+    pub multisig: Pubkey,
+    pub candidate: Pubkey,
+    pub expires_at: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct OwnerAdded {
+    pub multisig: Pubkey,
+    pub owner: Pubkey,
+    pub owners_count: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct OwnerRemoved {
+    pub multisig: Pubkey,
+    pub owner: Pubkey,
+    pub owners_count: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct OwnerReplaced {
+    pub multisig: Pubkey,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct OwnersReplaced {
+    pub multisig: Pubkey,
+    pub old_owners: Vec<Pubkey>,
+    pub new_owners: Vec<Pubkey>,
+    pub old_threshold: u8,
+    pub new_threshold: u8,
+    pub event_seq: u64,
+}
+
+// Owner candidate recorded by nominate_owner
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct OwnerNomination {
+    pub candidate: Pubkey,
+    // Last moment accept_ownership is allowed
+    pub expires_at: i64,
+}
+
+// Execution held by the large outflow circuit breaker
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LargeOutflowHold {
+    pub nonce: u64,
+    pub held_at: i64,
+}
+
+// Guardian confirmation of a held outflow. The payload hash keeps it from
+// covering a different proposal recreated under a freed legacy nonce.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LargeOutflowRelease {
+    pub nonce: u64,
+    pub tx_hash: [u8; 32],
+}
+
+// Membership, threshold or spending-limit change waiting out min_governance_delay
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct GovernanceChange {
+    pub action: GovernanceAction,
+    // Earliest time apply_governance_change may run it
+    pub eta: i64,
+}
+
+impl GovernanceChange {
+    // Largest action (ReplaceOwner) plus the eta
+    pub const SPACE: usize = 1 + 64 + 8;
+}
+
+// Arguments of the delayed admin instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub enum GovernanceAction {
+    AddOwner { new_owner: Pubkey },
+    RemoveOwner { owner: Pubkey, new_threshold: Option<u8> },
+    ReplaceOwner { old_owner: Pubkey, new_owner: Pubkey },
+    ChangeThreshold { new_threshold: u8 },
+    SetThresholdBps { threshold_bps: u16 },
+    SetGovernanceDelay { min_governance_delay: i64 },
+    SetDailyLimit { daily_limit: u64 },
+    SetMaxTxValue { max_tx_value: u64 },
+    NominateOwner { candidate: Pubkey },
+    SetWeightThreshold { weight_threshold: u64 },
+}
+
+// Proposal fields besides the instructions that change what execution does or
+// when it may happen, in tx_hash order. A field added to Transaction with that
+// effect belongs here too.
+#[derive(AnchorSerialize, Default)]
+struct ProposalTerms {
+    executor_reward: u64,
+    expires_at: i64,
+    depends_on: Option<u64>,
+    required_approvals: u8,
+    valid_from_slot: u64,
+    valid_until_slot: u64,
+    description: String,
+    lookup_keys_hash: [u8; 32],
+}
+
+// Execution urgency requested by the proposer. The program can't set compute
+// unit prices itself, relayers read this to decide how much to bid.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityHint {
+    Low,
+    Medium,
+    High,
+}
+
+// Kind of membership change carried by OwnershipChanged
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum OwnershipChangeType {
+    Added,
+    Removed,
+    // target is the incoming owner
+    Replaced,
+    // target is the default pubkey
+    ThresholdChanged,
+    // Whole owner set replaced through recovery, target is the default pubkey
+    Recovered,
+}
+
+// Single schema for every membership or threshold change, emitted next to
+// the more specific events so indexers can build an audit log from one type
+#[event]
+pub struct OwnershipChanged {
+    pub multisig: Pubkey,
+    pub change_type: OwnershipChangeType,
+    pub target: Pubkey,
+    pub old_threshold: u8,
+    pub new_threshold: u8,
+    pub owner_count: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct Ping {
+    pub multisig: Pubkey,
+    pub caller: Pubkey,
+    pub slot: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct AllowedProgramsChanged {
+    pub multisig: Pubkey,
+    pub program_id: Pubkey,
+    // false when the program was removed
+    pub allowed: bool,
+    pub count: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct AllowedRecipientsChanged {
+    pub multisig: Pubkey,
+    pub recipient: Pubkey,
+    // false when the recipient was removed
+    pub allowed: bool,
+    pub count: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct GovernanceChangeQueued {
+    pub multisig: Pubkey,
+    pub action: GovernanceAction,
+    pub eta: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct GovernanceChangeApplied {
+    pub multisig: Pubkey,
+    pub action: GovernanceAction,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct GovernanceChangeVetoed {
+    pub multisig: Pubkey,
+    pub action: GovernanceAction,
+    pub vetoed_by: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct GovernanceDelayChanged {
+    pub multisig: Pubkey,
+    pub old_delay: i64,
+    pub new_delay: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct TokenRegistered {
+    pub multisig: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct DuplicateAccountPolicyChanged {
+    pub multisig: Pubkey,
+    pub reject_duplicates: bool,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct RevokePolicyChanged {
+    pub multisig: Pubkey,
+    pub persist_timelock_on_revoke: bool,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ExpiryGraceChanged {
+    pub multisig: Pubkey,
+    pub expiry_grace: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ExecutorPolicyChanged {
+    pub multisig: Pubkey,
+    pub executor_must_be_owner: bool,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct MultisigClosed {
+    pub multisig: Pubkey,
+    pub creator: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct VetoOwnersChanged {
+    pub multisig: Pubkey,
+    pub veto_owners: Vec<Pubkey>,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct LargeOutflowThresholdChanged {
+    pub multisig: Pubkey,
+    pub large_outflow_bps: u16,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct LargeOutflowHeld {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    // Lamports the transaction's system transfers and executor reward move
+    pub amount: u64,
+    pub vault_balance: u64,
+    // Last moment a guardian can confirm
+    pub confirm_by: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct LargeOutflowConfirmed {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub guardian: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct TransactionVetoed {
+    pub transaction: Pubkey,
+    pub veto_owner: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct MaxTxValueChanged {
+    pub multisig: Pubkey,
+    pub max_tx_value: u64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ReadonlyThresholdChanged {
+    pub multisig: Pubkey,
+    pub readonly_threshold: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct GuardianPauseVoted {
+    pub multisig: Pubkey,
+    pub guardian: Pubkey,
+    pub paused: bool,
+    // Guardians currently voting for `paused`, including this one
+    pub votes: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct RequiredRolesChanged {
+    pub multisig: Pubkey,
+    pub required_roles: Vec<RoleRequirement>,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct OwnerRoleChanged {
+    pub multisig: Pubkey,
+    pub owner: Pubkey,
+    pub role: [u8; 16],
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct ApprovalTrackingChanged {
+    pub multisig: Pubkey,
+    pub enabled: bool,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct RetentionPolicyChanged {
+    pub multisig: Pubkey,
+    pub retain_executed_data: bool,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct DelegateChanged {
+    pub multisig: Pubkey,
+    pub owner: Pubkey,
+    pub delegate: Option<Pubkey>,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct MultisigMigrated {
+    pub multisig: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct GuardiansChanged {
+    pub multisig: Pubkey,
+    pub guardians: Vec<Pubkey>,
+    pub guardian_threshold: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct RecoveryProposed {
+    pub multisig: Pubkey,
+    pub recovery: Pubkey,
+    pub guardian: Pubkey,
+    pub new_owners: Vec<Pubkey>,
+    pub new_threshold: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct RecoveryApproved {
+    pub multisig: Pubkey,
+    pub recovery: Pubkey,
+    pub guardian: Pubkey,
+    pub eta: i64,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct RecoveryFinalized {
+    pub multisig: Pubkey,
+    pub new_owners: Vec<Pubkey>,
+    pub new_threshold: u8,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct RecoveryCancelled {
+    pub multisig: Pubkey,
+    pub recovery: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub event_seq: u64,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Invalid threshold")]
+    InvalidThreshold,
+    #[msg("Duplicate owners")]
+    DuplicateOwners,
+    #[msg("No owner provided")]
+    NoOwners,
+    #[msg("The proposer is not an owner")]
     NotAnOwner,
     #[msg("Not an owner")]
     NotOwner,
@@ -424,4 +6351,166 @@ pub enum ErrorCode {
     AlreadyAnOwner,
     #[msg("Too many owners")]
     TooManyOwners,
-}
\ No newline at end of file
+    #[msg("No approval from this owner to revoke")]
+    ApprovalNotFound,
+    #[msg("Too many instructions in transaction")]
+    TooManyInstructions,
+    #[msg("Source token account must hold the mint and be owned by the multisig")]
+    InvalidTokenAccount,
+    #[msg("Transaction has expired")]
+    TransactionExpired,
+    #[msg("Timelock has not elapsed yet")]
+    TimelockNotElapsed,
+    #[msg("Supplied accounts do not match the approved instruction")]
+    AccountMismatch,
+    #[msg("Transaction hash does not match the proposal")]
+    HashMismatch,
+    #[msg("Weights must be empty or match the number of owners")]
+    WeightsMismatch,
+    #[msg("Multisig is paused")]
+    MultisigPaused,
+    #[msg("Only the creator can perform this action")]
+    NotCreator,
+    #[msg("Transaction has not been executed")]
+    NotExecuted,
+    #[msg("Transfer would exceed the daily limit")]
+    DailyLimitExceeded,
+    #[msg("Too many transactions in batch")]
+    BatchTooLarge,
+    #[msg("Transaction was rejected by the owners")]
+    TransactionRejected,
+    #[msg("Already rejected")]
+    AlreadyRejected,
+    #[msg("Not an initialized system nonce account")]
+    InvalidNonceAccount,
+    #[msg("Reentrant call to execute_transaction")]
+    ReentrancyDetected,
+    #[msg("No matching ed25519 signature verification found")]
+    InvalidSignature,
+    #[msg("Not a guardian")]
+    NotGuardian,
+    #[msg("Too many guardians")]
+    TooManyGuardians,
+    #[msg("Recovery not approved by enough guardians or delay not elapsed")]
+    RecoveryNotReady,
+    #[msg("Too many open transactions")]
+    TooManyOpenTransactions,
+    #[msg("Nonce buffer is full, release executed nonces first")]
+    NonceBufferFull,
+    #[msg("Nonce is not in use")]
+    NonceNotFound,
+    #[msg("Transaction TTL cannot be negative")]
+    InvalidTtl,
+    #[msg("Proposals may not call the multisig program")]
+    SelfCallNotAllowed,
+    #[msg("Nonce must equal the multisig's current seq")]
+    UnexpectedNonce,
+    #[msg("Chunk must start at the next unexecuted instruction and stay in bounds")]
+    InvalidChunk,
+    #[msg("Threshold bps must be between 1 and 10000")]
+    InvalidThresholdBps,
+    #[msg("Rate limit must be at most 32 per window with a positive window")]
+    InvalidRateLimit,
+    #[msg("Every owner must sign")]
+    MissingOwnerSignature,
+    #[msg("Approval too soon after the previous one")]
+    VotingTooFast,
+    #[msg("Vote spacing cannot be negative")]
+    InvalidVoteSpacing,
+    #[msg("Multisig already uses the current layout")]
+    AlreadyMigrated,
+    #[msg("Target program is not on the multisig's allowlist")]
+    ProgramNotAllowed,
+    #[msg("Entry is already on the allowlist")]
+    AlreadyAllowed,
+    #[msg("Allowlist is full")]
+    AllowlistFull,
+    #[msg("Recipient is not on the multisig's allowlist")]
+    RecipientNotAllowed,
+    #[msg("Max data size must be between 1 and 1024 bytes")]
+    InvalidDataSize,
+    #[msg("Governance delay cannot be negative")]
+    InvalidGovernanceDelay,
+    #[msg("Another governance change is already pending")]
+    GovernanceChangePending,
+    #[msg("No governance change is pending")]
+    NoPendingGovernanceChange,
+    #[msg("Governance delay has not elapsed yet")]
+    GovernanceChangeNotReady,
+    #[msg("Lookup table account missing, invalid or too short for the referenced index")]
+    InvalidLookupTable,
+    #[msg("Minimum owner count must be at least 1")]
+    InvalidMinOwners,
+    #[msg("Owner count would drop below the multisig's minimum")]
+    TooFewOwners,
+    #[msg("The transaction this one depends on has not been executed")]
+    DependencyNotExecuted,
+    #[msg("Mint is already registered")]
+    TokenAlreadyRegistered,
+    #[msg("Too many tracked mints")]
+    TooManyTrackedMints,
+    #[msg("Account appears more than once in an instruction")]
+    DuplicateInstructionAccount,
+    #[msg("Repeated account has conflicting signer or writable flags")]
+    InconsistentAccountFlags,
+    #[msg("Multisig still has open transactions or funds in its vault")]
+    MultisigNotEmpty,
+    #[msg("Delegate is an owner or already delegated to by another owner")]
+    DelegateInUse,
+    #[msg("Owner count differs from the one the approver expected")]
+    OwnerSetChanged,
+    #[msg("Remaining accounts don't include every account and program the transaction uses")]
+    MissingExecutionAccounts,
+    #[msg("Role requirements must name distinct, non-empty roles with a non-zero count")]
+    InvalidRoleRequirement,
+    #[msg("Approvers don't cover every required role")]
+    RoleQuorumNotMet,
+    #[msg("Description exceeds 128 bytes")]
+    DescriptionTooLong,
+    #[msg("Transaction transfers more SOL than max_tx_value allows")]
+    TransactionValueExceedsLimit,
+    #[msg("Not allowed while a governance delay is set")]
+    GovernanceDelayActive,
+    #[msg("valid_from_slot is after valid_until_slot")]
+    InvalidSlotWindow,
+    #[msg("Current slot is outside the transaction's execution window")]
+    SlotOutsideWindow,
+    #[msg("Proposer can't cover the transaction account's rent-exempt minimum")]
+    InsufficientRentFunds,
+    #[msg("Transaction was vetoed")]
+    Vetoed,
+    #[msg("Too many owners have a delegate")]
+    TooManyDelegates,
+    #[msg("Only an owner can execute transactions for this multisig")]
+    ExecutorNotOwner,
+    #[msg("Transfer source and destination are the same account")]
+    SelfTransferNotAllowed,
+    #[msg("Expiry grace period must not be negative")]
+    InvalidExpiryGrace,
+    #[msg("Vault and multisig signatures outside this program need the dedicated transfer helpers")]
+    PdaSignerNotAllowed,
+    #[msg("No pending nomination for this key")]
+    NoPendingNomination,
+    #[msg("Owner nomination has expired")]
+    NominationExpired,
+    #[msg("Large outflow is held until a guardian confirms it")]
+    LargeOutflowNotConfirmed,
+    #[msg("Transaction isn't held for confirmation, or the confirmation window has passed")]
+    LargeOutflowNotHeld,
+    #[msg("Guardians must be set first")]
+    NoGuardians,
+    #[msg("Executor reward exceeds MAX_EXECUTOR_REWARD")]
+    ExecutorRewardTooHigh,
+    #[msg("A lookup table reference resolves to a different address than at proposal time")]
+    LookupKeysMismatch,
+    #[msg("Owners no longer hold enough of a required role to meet the role quorum")]
+    RoleQuorumUnreachable,
+    #[msg("depends_on must name another open proposal")]
+    InvalidDependency,
+    #[msg("An open proposal depends on this transaction")]
+    TransactionPinned,
+    #[msg("Part of the batch has already executed")]
+    BatchInProgress,
+    #[msg("close_multisig must be the proposal's last instruction")]
+    CloseMultisigNotLast,
+}
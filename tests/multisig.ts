@@ -1,5 +1,14 @@
 import * as anchor from "@coral-xyz/anchor";
-import { Program } from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  LAMPORTS_PER_SOL,
+  Ed25519Program,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+} from "@solana/web3.js";
+import { assert } from "chai";
 import { Multisig } from "../target/types/multisig";
 
 describe("multisig", () => {
@@ -14,3 +23,2404 @@ describe("multisig", () => {
     console.log("Your transaction signature", tx);
   });
 });
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.multisig as Program<Multisig>;
+
+const multisigPda = (multisigId: BN) =>
+  PublicKey.findProgramAddressSync(
+    [Buffer.from("multisig"), multisigId.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  )[0];
+
+const transactionPda = (multisig: PublicKey, nonce: BN) =>
+  PublicKey.findProgramAddressSync(
+    [Buffer.from("transaction"), multisig.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  )[0];
+
+const vaultPda = (multisig: PublicKey) =>
+  PublicKey.findProgramAddressSync([Buffer.from("vault"), multisig.toBuffer()], program.programId)[0];
+
+const fund = async (keypair: Keypair) => {
+  const sig = await provider.connection.requestAirdrop(keypair.publicKey, 2 * LAMPORTS_PER_SOL);
+  await provider.connection.confirmTransaction(sig);
+};
+
+const initializeMultisig = async (owners: Keypair[], threshold: number, minDelay = 0) => {
+  const multisigId = new BN(Date.now());
+  const multisig = multisigPda(multisigId);
+
+  await program.methods
+    .initialize(multisigId, owners.map((o) => o.publicKey), threshold, new BN(minDelay), [], new Array(32).fill(0), new BN(0), true, false, 1024, 1, null, new BN(0))
+    .accountsPartial({
+      multisig,
+      creator: owners[0].publicKey,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([owners[0]])
+    .rpc();
+
+  return { multisigId, multisig };
+};
+
+const deposit = async (from: Keypair, multisig: PublicKey, lamports: number) => {
+  await provider.sendAndConfirm(
+    new anchor.web3.Transaction().add(
+      SystemProgram.transfer({ fromPubkey: from.publicKey, toPubkey: vaultPda(multisig), lamports })
+    ),
+    [from]
+  );
+};
+
+// Proposals must use the multisig's current seq as their nonce
+const nextNonce = async (multisig: PublicKey) => (await program.account.multisig.fetch(multisig)).seq;
+
+// Records `signer`'s approval of an existing proposal
+const approve = async (multisigId: BN, nonce: BN, signer: Keypair) => {
+  const multisig = multisigPda(multisigId);
+  const transaction = transactionPda(multisig, nonce);
+
+  const { txHash } = await program.account.transaction.fetch(transaction);
+
+  await program.methods
+    .approveTransaction(multisigId, nonce, txHash, null, 0)
+    .accountsPartial({ owner: signer.publicKey, multisig, transaction })
+    .signers([signer])
+    .rpc();
+};
+
+const execute = async (
+  multisigId: BN,
+  nonce: BN,
+  executor: Keypair,
+  remainingAccounts: anchor.web3.AccountMeta[]
+) => {
+  const multisig = multisigPda(multisigId);
+
+  await program.methods
+    .executeTransaction(multisigId, nonce, 0, 0)
+    .accountsPartial({ executor: executor.publicKey, multisig, transaction: transactionPda(multisig, nonce) })
+    .remainingAccounts(remainingAccounts)
+    .signers([executor])
+    .rpc();
+};
+
+// Approves an existing proposal with every signer and executes it
+const approveAndExecute = async (
+  multisigId: BN,
+  nonce: BN,
+  signers: Keypair[],
+  remainingAccounts: anchor.web3.AccountMeta[]
+) => {
+  for (const signer of signers) {
+    await approve(multisigId, nonce, signer);
+  }
+
+  await execute(multisigId, nonce, signers[0], remainingAccounts);
+};
+
+// Accounts an instruction calling back into this program needs at execution
+const selfCallAccounts = (multisig: PublicKey) => [
+  { pubkey: multisig, isSigner: false, isWritable: true },
+  { pubkey: program.programId, isSigner: false, isWritable: false },
+];
+
+// Proposes a single instruction and returns the proposal's nonce
+const propose = async (multisigId: BN, ix: anchor.web3.TransactionInstruction, proposer: Keypair) => {
+  const multisig = multisigPda(multisigId);
+  const nonce = await nextNonce(multisig);
+
+  await program.methods
+    .createTransaction(multisigId, nonce, [
+      { programId: ix.programId, accounts: ix.keys, data: ix.data },
+    ], new BN(0), new BN(0), null, null, null, new BN(0), new BN(0), 0)
+    .accountsPartial({
+      proposer: proposer.publicKey,
+      multisig,
+      transaction: transactionPda(multisig, nonce),
+      nonceAccount: null,
+      recentBlockhashes: null,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([proposer])
+    .rpc();
+
+  return nonce;
+};
+
+// Proposes an instruction against this program, approves it with every signer and executes it
+const proposeAndExecute = async (
+  multisigId: BN,
+  ix: anchor.web3.TransactionInstruction,
+  signers: Keypair[]
+) => {
+  const nonce = await propose(multisigId, ix, signers[0]);
+  await approveAndExecute(multisigId, nonce, signers, selfCallAccounts(multisigPda(multisigId)));
+
+  return nonce;
+};
+
+describe("owner management", () => {
+  it("rejects removing an owner when the threshold would become unreachable", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2);
+
+    // 2-of-2 minus one owner leaves a threshold that can never be met
+    const removeIx = await program.methods
+      .removeOwner(multisigId, owners[1].publicKey, null)
+      .accountsPartial({ multisig })
+      .instruction();
+
+    try {
+      await proposeAndExecute(multisigId, removeIx, owners);
+      assert.fail("remove_owner should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidThreshold");
+    }
+
+    let account = await program.account.multisig.fetch(multisig);
+    assert.equal(account.owners.length, 2);
+    assert.equal(account.threshold, 2);
+
+    // Lowering the threshold in the same instruction keeps the wallet usable
+    const removeAndLowerIx = await program.methods
+      .removeOwner(multisigId, owners[1].publicKey, 1)
+      .accountsPartial({ multisig })
+      .instruction();
+
+    await proposeAndExecute(multisigId, removeAndLowerIx, owners);
+
+    account = await program.account.multisig.fetch(multisig);
+    assert.equal(account.owners.length, 1);
+    assert.equal(account.threshold, 1);
+  });
+});
+
+describe("transaction lifecycle", () => {
+  it("closes an executed transaction and refunds the proposer", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const changeThresholdIx = await program.methods
+      .changeThreshold(multisigId, 1)
+      .accountsPartial({ multisig })
+      .instruction();
+    const nonce = await proposeAndExecute(multisigId, changeThresholdIx, [owner]);
+    const transaction = transactionPda(multisig, nonce);
+
+    const rent = await provider.connection.getBalance(transaction);
+    const balanceBefore = await provider.connection.getBalance(owner.publicKey);
+
+    // Sent by the provider wallet so the proposer pays no fees
+    await program.methods
+      .closeTransaction(multisigId, nonce)
+      .accountsPartial({ multisig, transaction, proposer: owner.publicKey })
+      .rpc();
+
+    const balanceAfter = await provider.connection.getBalance(owner.publicKey);
+    assert.equal(balanceAfter - balanceBefore, rent);
+    assert.isNull(await provider.connection.getAccountInfo(transaction));
+  });
+
+  it("rejects a stored instruction that re-enters execute_transaction", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const propose = async (nonce: BN, instructions) =>
+      program.methods
+        .createTransaction(multisigId, nonce, instructions, new BN(0), new BN(0), null, null, null, new BN(0), new BN(0), 0)
+        .accountsPartial({
+          proposer: owner.publicKey,
+          multisig,
+          transaction: transactionPda(multisig, nonce),
+          nonceAccount: null,
+          recentBlockhashes: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+
+    // An approved, empty proposal the outer one will try to execute from inside its CPI
+    const innerNonce = await nextNonce(multisig);
+    const innerTransaction = transactionPda(multisig, innerNonce);
+    await propose(innerNonce, []);
+    const { txHash } = await program.account.transaction.fetch(innerTransaction);
+    await program.methods
+      .approveTransaction(multisigId, innerNonce, txHash, null, 0)
+      .accountsPartial({ owner: owner.publicKey, multisig, transaction: innerTransaction })
+      .signers([owner])
+      .rpc();
+
+    // The multisig PDA is signed for during execution, so it can act as the executor
+    const reenterIx = await program.methods
+      .executeTransaction(multisigId, innerNonce, 0, 0)
+      .accountsPartial({ executor: multisig, multisig, transaction: innerTransaction })
+      .instruction();
+
+    const outerNonce = innerNonce.addn(1);
+    await propose(outerNonce, [
+      { programId: reenterIx.programId, accounts: reenterIx.keys, data: reenterIx.data },
+    ]);
+
+    try {
+      await approveAndExecute(multisigId, outerNonce, [owner], [
+        ...reenterIx.keys.map((key) => ({ ...key, isSigner: false })),
+        { pubkey: program.programId, isSigner: false, isWritable: false },
+      ]);
+      assert.fail("execute_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "ReentrancyDetected");
+    }
+
+    const account = await program.account.transaction.fetch(innerTransaction);
+    assert.isFalse(account.didExecute);
+  });
+});
+
+describe("approval revocation", () => {
+  it("clears the timelock when a revocation drops below the threshold and restarts it once re-reached", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2);
+    const nonce = await nextNonce(multisig);
+    const transaction = transactionPda(multisig, nonce);
+
+    await program.methods
+      .createTransaction(multisigId, nonce, [], new BN(0), new BN(0), null, null, null, new BN(0), new BN(0), 0)
+      .accountsPartial({
+        proposer: owners[0].publicKey,
+        multisig,
+        transaction,
+        nonceAccount: null,
+        recentBlockhashes: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owners[0]])
+      .rpc();
+
+    const { txHash } = await program.account.transaction.fetch(transaction);
+    const approve = (owner: Keypair) =>
+      program.methods
+        .approveTransaction(multisigId, nonce, txHash, null, 0)
+        .accountsPartial({ owner: owner.publicKey, multisig, transaction })
+        .signers([owner])
+        .rpc();
+
+    for (const owner of owners) {
+      await approve(owner);
+    }
+    assert.isFalse((await program.account.transaction.fetch(transaction)).eta.isZero());
+
+    await program.methods
+      .revokeApproval(multisigId, nonce)
+      .accountsPartial({ owner: owners[1].publicKey, multisig, transaction })
+      .signers([owners[1]])
+      .rpc();
+    assert.isTrue((await program.account.transaction.fetch(transaction)).eta.isZero());
+
+    // Re-reaching the threshold queues the transaction again
+    await approve(owners[1]);
+    const account = await program.account.transaction.fetch(transaction);
+    assert.isFalse(account.eta.isZero());
+    // Bits 0 and 1: both owners
+    assert.equal(account.approvalBitmap.toNumber(), 0b11);
+  });
+});
+
+describe("duplicate instruction accounts", () => {
+  const proposeWithAccounts = async (owner: Keypair, multisigId: BN, accounts: anchor.web3.AccountMeta[]) => {
+    const multisig = multisigPda(multisigId);
+    const nonce = await nextNonce(multisig);
+
+    await program.methods
+      .createTransaction(multisigId, nonce, [
+        { programId: SystemProgram.programId, accounts, data: Buffer.alloc(0) },
+      ], new BN(0), new BN(0), null, null, null, new BN(0), new BN(0), 0)
+      .accountsPartial({
+        proposer: owner.publicKey,
+        multisig,
+        transaction: transactionPda(multisig, nonce),
+        nonceAccount: null,
+        recentBlockhashes: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+  };
+
+  it("rejects the same key as both writable and read-only", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+    const { multisigId } = await initializeMultisig([owner], 1);
+
+    const key = Keypair.generate().publicKey;
+    try {
+      await proposeWithAccounts(owner, multisigId, [
+        { pubkey: key, isSigner: false, isWritable: true },
+        { pubkey: key, isSigner: false, isWritable: false },
+      ]);
+      assert.fail("create_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InconsistentAccountFlags");
+    }
+
+    // Consistent repeats are fine by default
+    await proposeWithAccounts(owner, multisigId, [
+      { pubkey: key, isSigner: false, isWritable: true },
+      { pubkey: key, isSigner: false, isWritable: true },
+    ]);
+  });
+
+  it("rejects any repeated key when the multisig is strict", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const strictIx = await program.methods
+      .setRejectDuplicateAccounts(multisigId, true)
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, strictIx, [owner]);
+
+    const key = Keypair.generate().publicKey;
+    try {
+      await proposeWithAccounts(owner, multisigId, [
+        { pubkey: key, isSigner: false, isWritable: true },
+        { pubkey: key, isSigner: false, isWritable: true },
+      ]);
+      assert.fail("create_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "DuplicateInstructionAccount");
+    }
+  });
+});
+
+describe("durable nonces", () => {
+  const NONCE_ACCOUNT_LENGTH = 80;
+
+  const proposeWithNonceAccount = async (owner: Keypair, multisigId: BN, nonceAccount: PublicKey) => {
+    const multisig = multisigPda(multisigId);
+    const nonce = await nextNonce(multisig);
+
+    await program.methods
+      .createTransaction(multisigId, nonce, [], new BN(0), new BN(0), null, null, null, new BN(0), new BN(0), 0)
+      .accountsPartial({
+        proposer: owner.publicKey,
+        multisig,
+        transaction: transactionPda(multisig, nonce),
+        nonceAccount,
+        recentBlockhashes: anchor.web3.SYSVAR_RECENT_BLOCKHASHES_PUBKEY,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+  };
+
+  it("rejects an uninitialized nonce account", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+    const { multisigId } = await initializeMultisig([owner], 1);
+
+    // Right size and owner, but never initialized as a nonce
+    const nonceAccount = Keypair.generate();
+    const tx = new anchor.web3.Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: owner.publicKey,
+        newAccountPubkey: nonceAccount.publicKey,
+        lamports: await provider.connection.getMinimumBalanceForRentExemption(NONCE_ACCOUNT_LENGTH),
+        space: NONCE_ACCOUNT_LENGTH,
+        programId: SystemProgram.programId,
+      })
+    );
+    await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [owner, nonceAccount]);
+
+    try {
+      await proposeWithNonceAccount(owner, multisigId, nonceAccount.publicKey);
+      assert.fail("create_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidNonceAccount");
+    }
+  });
+
+  it("rejects a nonce account whose authority is not the multisig", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+    const { multisigId } = await initializeMultisig([owner], 1);
+
+    const nonceAccount = Keypair.generate();
+    const tx = new anchor.web3.Transaction().add(
+      ...SystemProgram.createNonceAccount({
+        fromPubkey: owner.publicKey,
+        noncePubkey: nonceAccount.publicKey,
+        authorizedPubkey: owner.publicKey,
+        lamports: await provider.connection.getMinimumBalanceForRentExemption(NONCE_ACCOUNT_LENGTH),
+      }).instructions
+    );
+    await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [owner, nonceAccount]);
+
+    try {
+      await proposeWithNonceAccount(owner, multisigId, nonceAccount.publicKey);
+      assert.fail("create_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidNonceAuthority");
+    }
+  });
+});
+
+describe("token transfers", () => {
+  const TOKEN_PROGRAM_ID = new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+  const MINT_SIZE = 82;
+  const ACCOUNT_SIZE = 165;
+
+  // Minimal SPL token instructions so the tests don't need @solana/spl-token
+  const createTokenAccount = async (payer: Keypair, space: number, init: (key: PublicKey) => anchor.web3.TransactionInstruction) => {
+    const account = Keypair.generate();
+    const lamports = await provider.connection.getMinimumBalanceForRentExemption(space);
+    const tx = new anchor.web3.Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: payer.publicKey,
+        newAccountPubkey: account.publicKey,
+        lamports,
+        space,
+        programId: TOKEN_PROGRAM_ID,
+      }),
+      init(account.publicKey)
+    );
+    await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [payer, account]);
+    return account.publicKey;
+  };
+
+  const createMint = (payer: Keypair, decimals: number) =>
+    createTokenAccount(payer, MINT_SIZE, (mint) => {
+      // InitializeMint2: tag 20, decimals, mint authority, no freeze authority
+      const data = Buffer.concat([Buffer.from([20, decimals]), payer.publicKey.toBuffer(), Buffer.from([0])]);
+      return new anchor.web3.TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [{ pubkey: mint, isSigner: false, isWritable: true }],
+        data,
+      });
+    });
+
+  const createAccount = (payer: Keypair, mint: PublicKey, owner: PublicKey) =>
+    createTokenAccount(payer, ACCOUNT_SIZE, (account) => {
+      // InitializeAccount3: tag 18, owner
+      const data = Buffer.concat([Buffer.from([18]), owner.toBuffer()]);
+      return new anchor.web3.TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [
+          { pubkey: account, isSigner: false, isWritable: true },
+          { pubkey: mint, isSigner: false, isWritable: false },
+        ],
+        data,
+      });
+    });
+
+  const mintTo = async (authority: Keypair, mint: PublicKey, destination: PublicKey, amount: number) => {
+    // MintTo: tag 7, amount
+    const data = Buffer.concat([Buffer.from([7]), new BN(amount).toArrayLike(Buffer, "le", 8)]);
+    const tx = new anchor.web3.Transaction().add(
+      new anchor.web3.TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [
+          { pubkey: mint, isSigner: false, isWritable: true },
+          { pubkey: destination, isSigner: false, isWritable: true },
+          { pubkey: authority.publicKey, isSigner: true, isWritable: false },
+        ],
+        data,
+      })
+    );
+    await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [authority]);
+  };
+
+  it("proposes and executes a token transfer out of the multisig", async () => {
+    const owner = Keypair.generate();
+    const recipient = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const mint = await createMint(owner, 6);
+    const source = await createAccount(owner, mint, multisig);
+    const destination = await createAccount(owner, mint, recipient.publicKey);
+    await mintTo(owner, mint, source, 1_000_000);
+
+    const nonce = await nextNonce(multisig);
+    await program.methods
+      .proposeTokenTransfer(multisigId, nonce, new BN(250_000), new BN(0))
+      .accountsPartial({
+        proposer: owner.publicKey,
+        multisig,
+        transaction: transactionPda(multisig, nonce),
+        mint,
+        source,
+        destination,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    await approveAndExecute(multisigId, nonce, [owner], [
+      { pubkey: source, isSigner: false, isWritable: true },
+      { pubkey: mint, isSigner: false, isWritable: false },
+      { pubkey: destination, isSigner: false, isWritable: true },
+      { pubkey: multisig, isSigner: false, isWritable: false },
+      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    ]);
+
+    const sourceBalance = await provider.connection.getTokenAccountBalance(source);
+    const destinationBalance = await provider.connection.getTokenAccountBalance(destination);
+    assert.equal(sourceBalance.value.amount, "750000");
+    assert.equal(destinationBalance.value.amount, "250000");
+  });
+
+  it("rejects a source token account not owned by the multisig", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const mint = await createMint(owner, 6);
+    const source = await createAccount(owner, mint, owner.publicKey);
+    const destination = await createAccount(owner, mint, owner.publicKey);
+
+    const nonce = await nextNonce(multisig);
+    try {
+      await program.methods
+        .proposeTokenTransfer(multisigId, nonce, new BN(1), new BN(0))
+        .accountsPartial({
+          proposer: owner.publicKey,
+          multisig,
+          transaction: transactionPda(multisig, nonce),
+          mint,
+          source,
+          destination,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      assert.fail("propose_token_transfer should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidTokenAccount");
+    }
+  });
+});
+
+describe("execution account checks", () => {
+  it("executes when the supplied accounts carry the approved flags", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await proposeAndExecute(multisigId, ix, [owner]);
+
+    assert.isTrue((await program.account.transaction.fetch(transactionPda(multisig, nonce))).didExecute);
+  });
+
+  it("rejects an account supplied without the approved writable flag", async () => {
+    const owner = Keypair.generate();
+    const target = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const ix = new anchor.web3.TransactionInstruction({
+      programId: SystemProgram.programId,
+      keys: [{ pubkey: target.publicKey, isSigner: false, isWritable: true }],
+      data: Buffer.alloc(0),
+    });
+    const nonce = await propose(multisigId, ix, owner);
+    await approve(multisigId, nonce, owner);
+
+    try {
+      await execute(multisigId, nonce, owner, [
+        { pubkey: target.publicKey, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ]);
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "AccountMismatch");
+    }
+
+    assert.isFalse((await program.account.transaction.fetch(transactionPda(multisig, nonce))).didExecute);
+  });
+});
+
+describe("off-chain approvals", () => {
+  const approvalMessage = (multisig: PublicKey, nonce: BN, txHash: number[]) =>
+    Buffer.concat([
+      Buffer.from("multisig-approval"),
+      multisig.toBuffer(),
+      nonce.toArrayLike(Buffer, "le", 8),
+      Buffer.from(txHash),
+    ]);
+
+  // Precompile instruction verifying `signer`'s signature over `message`, and
+  // the approval that claims the signature for `owner`
+  const signApproval = (signer: Keypair, owner: PublicKey, message: Buffer) => {
+    const verifyIx = Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message });
+    // 16-byte offsets header, then the public key and the signature
+    const signature = Array.from(verifyIx.data.subarray(48, 112));
+    return { verifyIx, approval: { owner, signature } };
+  };
+
+  // 2-of-2 multisig with a proposal the first owner approved on-chain
+  const setup = async (minDelay = 0) => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await fund(owners[0]);
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2, minDelay);
+
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await propose(multisigId, ix, owners[0]);
+    await approve(multisigId, nonce, owners[0]);
+
+    const { txHash } = await program.account.transaction.fetch(transactionPda(multisig, nonce));
+    return { owners, multisigId, multisig, nonce, txHash };
+  };
+
+  const executeWithSignature = (
+    multisigId: BN,
+    nonce: BN,
+    executor: Keypair,
+    signed: ReturnType<typeof signApproval>
+  ) => {
+    const multisig = multisigPda(multisigId);
+
+    return program.methods
+      .executeWithSignatures(multisigId, nonce, [signed.approval])
+      .accountsPartial({
+        executor: executor.publicKey,
+        multisig,
+        transaction: transactionPda(multisig, nonce),
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .remainingAccounts(selfCallAccounts(multisig))
+      .preInstructions([signed.verifyIx])
+      .signers([executor])
+      .rpc();
+  };
+
+  it("counts an owner's signature in place of an on-chain approval", async () => {
+    const { owners, multisigId, multisig, nonce, txHash } = await setup();
+
+    const signed = signApproval(owners[1], owners[1].publicKey, approvalMessage(multisig, nonce, txHash));
+    await executeWithSignature(multisigId, nonce, owners[0], signed);
+
+    assert.equal((await program.account.multisig.fetch(multisig)).threshold, 1);
+  });
+
+  it("rejects a signature over another proposal's message", async () => {
+    const { owners, multisigId, multisig, nonce, txHash } = await setup();
+
+    const otherNonce = nonce.addn(1);
+    const signed = signApproval(owners[1], owners[1].publicKey, approvalMessage(multisig, otherNonce, txHash));
+
+    try {
+      await executeWithSignature(multisigId, nonce, owners[0], signed);
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidSignature");
+    }
+  });
+
+  it("rejects a signature made by a key other than the claimed owner", async () => {
+    const { owners, multisigId, multisig, nonce, txHash } = await setup();
+
+    const impostor = Keypair.generate();
+    const signed = signApproval(impostor, owners[1].publicKey, approvalMessage(multisig, nonce, txHash));
+
+    try {
+      await executeWithSignature(multisigId, nonce, owners[0], signed);
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidSignature");
+    }
+
+    assert.equal((await program.account.multisig.fetch(multisig)).threshold, 2);
+  });
+
+  it("starts the timelock rather than executing when the multisig has a delay", async () => {
+    const { owners, multisigId, multisig, nonce, txHash } = await setup(3600);
+    const transaction = transactionPda(multisig, nonce);
+
+    const signed = signApproval(owners[1], owners[1].publicKey, approvalMessage(multisig, nonce, txHash));
+    await executeWithSignature(multisigId, nonce, owners[0], signed);
+
+    let account = await program.account.transaction.fetch(transaction);
+    assert.notEqual(account.eta.toNumber(), 0);
+    assert.isFalse(account.didExecute);
+
+    // The delay counts from here. A different executor keeps the retry from
+    // being deduplicated as the same transaction
+    try {
+      await executeWithSignature(multisigId, nonce, owners[1], signed);
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "TimelockNotElapsed");
+    }
+
+    account = await program.account.transaction.fetch(transaction);
+    assert.isFalse(account.didExecute);
+  });
+});
+
+describe("social recovery", () => {
+  const recoveryPda = (multisig: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("recovery"), multisig.toBuffer()], program.programId)[0];
+
+  // Single-owner multisig with one guardian who has opened a recovery to a new key
+  const openRecovery = async () => {
+    const owner = Keypair.generate();
+    const guardian = Keypair.generate();
+    const newOwner = Keypair.generate();
+    await Promise.all([owner, guardian].map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const setGuardiansIx = await program.methods
+      .setGuardians(multisigId, [guardian.publicKey], 1)
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, setGuardiansIx, [owner]);
+
+    const recovery = recoveryPda(multisig);
+    await program.methods
+      .recover(multisigId, [newOwner.publicKey], 1)
+      .accountsPartial({ guardian: guardian.publicKey, multisig, recovery, systemProgram: SystemProgram.programId })
+      .signers([guardian])
+      .rpc();
+
+    return { owner, guardian, multisigId, multisig, recovery };
+  };
+
+  it("lets an owner veto a recovery during its delay", async () => {
+    const { owner, guardian, multisigId, multisig, recovery } = await openRecovery();
+
+    // The guardian threshold is met, so the delay is running
+    assert.notEqual((await program.account.recovery.fetch(recovery)).eta.toNumber(), 0);
+
+    await program.methods
+      .cancelRecovery(multisigId)
+      .accountsPartial({ owner: owner.publicKey, multisig, recovery, proposer: guardian.publicKey })
+      .signers([owner])
+      .rpc();
+
+    assert.isNull(await program.account.recovery.fetchNullable(recovery));
+    const account = await program.account.multisig.fetch(multisig);
+    assert.deepEqual(account.owners.map((o) => o.toBase58()), [owner.publicKey.toBase58()]);
+  });
+
+  it("refuses to finalize before the delay has passed", async () => {
+    const { owner, guardian, multisigId, multisig, recovery } = await openRecovery();
+
+    try {
+      await program.methods
+        .finalizeRecovery(multisigId)
+        .accountsPartial({ payer: guardian.publicKey, multisig, recovery, proposer: guardian.publicKey })
+        .signers([guardian])
+        .rpc();
+      assert.fail("finalize_recovery should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "RecoveryNotReady");
+    }
+
+    const account = await program.account.multisig.fetch(multisig);
+    assert.deepEqual(account.owners.map((o) => o.toBase58()), [owner.publicKey.toBase58()]);
+  });
+
+  it("rejects a veto from someone who isn't an owner", async () => {
+    const { guardian, multisigId, multisig, recovery } = await openRecovery();
+
+    try {
+      await program.methods
+        .cancelRecovery(multisigId)
+        .accountsPartial({ owner: guardian.publicKey, multisig, recovery, proposer: guardian.publicKey })
+        .signers([guardian])
+        .rpc();
+      assert.fail("cancel_recovery should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "NotOwner");
+    }
+
+    assert.isNotNull(await program.account.recovery.fetchNullable(recovery));
+  });
+});
+
+describe("governance delay", () => {
+  // 1-of-2 multisig with a one hour governance delay and a queued threshold change
+  const queueThresholdChange = async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await fund(owners[0]);
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 1);
+
+    const setDelayIx = await program.methods
+      .setGovernanceDelay(multisigId, new BN(3600))
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, setDelayIx, [owners[0]]);
+
+    const changeIx = await program.methods.changeThreshold(multisigId, 2).accountsPartial({ multisig }).instruction();
+    await proposeAndExecute(multisigId, changeIx, [owners[0]]);
+
+    return { owners, multisigId, multisig };
+  };
+
+  it("lets any owner veto a queued change", async () => {
+    const { owners, multisigId, multisig } = await queueThresholdChange();
+
+    let account = await program.account.multisig.fetch(multisig);
+    assert.isNotNull(account.pendingGovernanceChange);
+    assert.equal(account.threshold, 1);
+
+    await program.methods
+      .vetoGovernanceChange(multisigId)
+      .accountsPartial({ owner: owners[1].publicKey, multisig })
+      .signers([owners[1]])
+      .rpc();
+
+    account = await program.account.multisig.fetch(multisig);
+    assert.isNull(account.pendingGovernanceChange);
+    assert.equal(account.threshold, 1);
+  });
+
+  it("refuses to apply a queued change before its eta", async () => {
+    const { owners, multisigId, multisig } = await queueThresholdChange();
+
+    try {
+      await program.methods
+        .applyGovernanceChange(multisigId)
+        .accountsPartial({ payer: owners[0].publicKey, multisig })
+        .signers([owners[0]])
+        .rpc();
+      assert.fail("apply_governance_change should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "GovernanceChangeNotReady");
+    }
+
+    const account = await program.account.multisig.fetch(multisig);
+    assert.isNotNull(account.pendingGovernanceChange);
+    assert.equal(account.threshold, 1);
+  });
+});
+
+describe("transaction value cap", () => {
+  // Single-owner multisig with a 0.1 SOL max_tx_value and 0.5 SOL in the vault
+  const setup = async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const capIx = await program.methods
+      .setMaxTxValue(multisigId, new BN(0.1 * LAMPORTS_PER_SOL))
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, capIx, [owner]);
+    await deposit(owner, multisig, 0.5 * LAMPORTS_PER_SOL);
+
+    return { owner, multisigId, multisig };
+  };
+
+  const transfer = async (owner: Keypair, multisigId: BN, recipient: PublicKey, lamports: number) => {
+    const multisig = multisigPda(multisigId);
+    const nonce = await nextNonce(multisig);
+
+    await program.methods
+      .proposeSolTransfer(multisigId, nonce, recipient, new BN(lamports), new BN(0))
+      .accountsPartial({
+        proposer: owner.publicKey,
+        multisig,
+        transaction: transactionPda(multisig, nonce),
+        vault: vaultPda(multisig),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    await approveAndExecute(multisigId, nonce, [owner], [
+      { pubkey: vaultPda(multisig), isSigner: false, isWritable: true },
+      { pubkey: recipient, isSigner: false, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ]);
+  };
+
+  it("executes a transfer within max_tx_value", async () => {
+    const { owner, multisigId } = await setup();
+    const recipient = Keypair.generate();
+
+    await transfer(owner, multisigId, recipient.publicKey, 0.05 * LAMPORTS_PER_SOL);
+
+    assert.equal(await provider.connection.getBalance(recipient.publicKey), 0.05 * LAMPORTS_PER_SOL);
+  });
+
+  it("rejects a transfer above max_tx_value", async () => {
+    const { owner, multisigId } = await setup();
+    const recipient = Keypair.generate();
+
+    try {
+      await transfer(owner, multisigId, recipient.publicKey, 0.2 * LAMPORTS_PER_SOL);
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "TransactionValueExceedsLimit");
+    }
+
+    assert.equal(await provider.connection.getBalance(recipient.publicKey), 0);
+  });
+});
+
+describe("approval bitmap", () => {
+  // 2-of-2 multisig with a proposal the first owner approved, after which the
+  // second owner's key was replaced
+  const approveThenReplace = async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    const replacement = Keypair.generate();
+    await fund(owners[0]);
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2);
+
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await propose(multisigId, ix, owners[0]);
+    await approve(multisigId, nonce, owners[0]);
+
+    const before = await program.account.transaction.fetch(transactionPda(multisig, nonce));
+    assert.equal(before.approvalBitmap.toNumber(), 0b01);
+
+    const replaceIx = await program.methods
+      .replaceOwner(multisigId, owners[1].publicKey, replacement.publicKey)
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, replaceIx, owners);
+
+    const account = await program.account.multisig.fetch(multisig);
+    assert.notEqual(account.ownerSetSeq.toNumber(), before.ownerSetSeq.toNumber());
+
+    return { owners, replacement, multisigId, multisig, nonce };
+  };
+
+  it("stops counting approvals recorded before the owner set changed", async () => {
+    const { owners, replacement, multisigId, multisig, nonce } = await approveThenReplace();
+
+    // Only the new key's approval counts, the first owner's is stale
+    await approve(multisigId, nonce, replacement);
+
+    try {
+      await execute(multisigId, nonce, owners[0], selfCallAccounts(multisig));
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "NotEnoughApprovals");
+    }
+  });
+
+  it("executes once the current owners approve again", async () => {
+    const { owners, replacement, multisigId, multisig, nonce } = await approveThenReplace();
+
+    await approveAndExecute(multisigId, nonce, [owners[0], replacement], selfCallAccounts(multisig));
+
+    assert.equal((await program.account.multisig.fetch(multisig)).threshold, 1);
+  });
+});
+
+describe("large outflow circuit breaker", () => {
+  // Single-owner multisig with a guardian and a 50% breaker, whose 0.6 SOL
+  // transfer out of a 1 SOL vault has just been held
+  const holdLargeTransfer = async () => {
+    const owner = Keypair.generate();
+    const guardian = Keypair.generate();
+    const recipient = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    const vault = vaultPda(multisig);
+
+    const setGuardiansIx = await program.methods
+      .setGuardians(multisigId, [guardian.publicKey], 1)
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, setGuardiansIx, [owner]);
+
+    // Anything above half the vault balance needs a guardian
+    const setThresholdIx = await program.methods
+      .setLargeOutflowThreshold(multisigId, 5_000)
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, setThresholdIx, [owner]);
+
+    await deposit(owner, multisig, LAMPORTS_PER_SOL);
+
+    const nonce = await nextNonce(multisig);
+    const transaction = transactionPda(multisig, nonce);
+    await program.methods
+      .proposeSolTransfer(multisigId, nonce, recipient.publicKey, new BN(0.6 * LAMPORTS_PER_SOL), new BN(0))
+      .accountsPartial({
+        proposer: owner.publicKey,
+        multisig,
+        transaction,
+        vault,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const transferAccounts = [
+      { pubkey: vault, isSigner: false, isWritable: true },
+      { pubkey: recipient.publicKey, isSigner: false, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ];
+
+    // The first attempt only pauses the multisig and records the hold
+    await approveAndExecute(multisigId, nonce, [owner], transferAccounts);
+
+    return { owner, guardian, recipient, multisigId, multisig, nonce, transaction, transferAccounts };
+  };
+
+  it("pauses on a large vault outflow until a guardian confirms it", async () => {
+    const { owner, guardian, recipient, multisigId, multisig, nonce, transaction, transferAccounts } =
+      await holdLargeTransfer();
+
+    let account = await program.account.multisig.fetch(multisig);
+    assert.isTrue(account.paused);
+    assert.equal(account.pendingLargeTx.nonce.toNumber(), nonce.toNumber());
+    assert.equal(await provider.connection.getBalance(recipient.publicKey), 0);
+
+    // Fresh executors keep the retries from repeating the holding transaction
+    try {
+      await execute(multisigId, nonce, Keypair.generate(), transferAccounts);
+      assert.fail("held outflow should not have executed");
+    } catch (err) {
+      assert.include(err.toString(), "MultisigPaused");
+    }
+
+    await program.methods
+      .confirmLargeOutflow(multisigId, nonce)
+      .accountsPartial({ guardian: guardian.publicKey, multisig, transaction })
+      .signers([guardian])
+      .rpc();
+
+    account = await program.account.multisig.fetch(multisig);
+    assert.isFalse(account.paused);
+    assert.isNull(account.pendingLargeTx);
+    assert.equal(account.releasedLargeTx.nonce.toNumber(), nonce.toNumber());
+
+    await execute(multisigId, nonce, Keypair.generate(), transferAccounts);
+
+    assert.equal(await provider.connection.getBalance(recipient.publicKey), 0.6 * LAMPORTS_PER_SOL);
+    assert.isTrue((await program.account.transaction.fetch(transaction)).didExecute);
+    assert.isNull((await program.account.multisig.fetch(multisig)).releasedLargeTx);
+  });
+
+  it("lifts the pause when the held proposal is cancelled", async () => {
+    const { owner, guardian, multisigId, multisig, nonce, transaction } = await holdLargeTransfer();
+
+    await program.methods
+      .cancelTransaction(multisigId, nonce)
+      .accountsPartial({ canceller: owner.publicKey, multisig, transaction, proposer: owner.publicKey })
+      .signers([owner])
+      .rpc();
+
+    const account = await program.account.multisig.fetch(multisig);
+    assert.isFalse(account.paused);
+    assert.isNull(account.pendingLargeTx);
+
+    // There is nothing left to confirm
+    try {
+      await program.methods
+        .confirmLargeOutflow(multisigId, nonce)
+        .accountsPartial({ guardian: guardian.publicKey, multisig, transaction })
+        .signers([guardian])
+        .rpc();
+      assert.fail("confirm_large_outflow should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "AccountNotInitialized");
+    }
+  });
+});
+
+describe("role quorum", () => {
+  // Roles are 16-byte labels, zero padded
+  const role = (label: string) => {
+    const bytes = Buffer.alloc(16);
+    bytes.write(label);
+    return Array.from(bytes);
+  };
+
+  // 1-of-2 multisig whose every execution needs one approver holding "SECURITY",
+  // which only the first owner holds
+  const setup = async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 1);
+
+    const setRoleIx = await program.methods
+      .setOwnerRole(multisigId, owners[0].publicKey, role("SECURITY"))
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, setRoleIx, [owners[0]]);
+
+    const requireIx = await program.methods
+      .setRequiredRoles(multisigId, [{ role: role("SECURITY"), count: 1 }])
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, requireIx, [owners[0]]);
+
+    return { owners, multisigId, multisig };
+  };
+
+  it("executes only once an approver holds the required role", async () => {
+    const { owners, multisigId, multisig } = await setup();
+
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await propose(multisigId, ix, owners[1]);
+    await approve(multisigId, nonce, owners[1]);
+
+    // The threshold is met, but not by a SECURITY owner
+    try {
+      await execute(multisigId, nonce, owners[0], selfCallAccounts(multisig));
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "RoleQuorumNotMet");
+    }
+
+    await approve(multisigId, nonce, owners[0]);
+    await execute(multisigId, nonce, owners[1], selfCallAccounts(multisig));
+
+    assert.isTrue((await program.account.transaction.fetch(transactionPda(multisig, nonce))).didExecute);
+  });
+
+  it("rejects owner and role changes that leave a required role unheld", async () => {
+    const { owners, multisigId, multisig } = await setup();
+
+    const removeIx = await program.methods
+      .removeOwner(multisigId, owners[0].publicKey, null)
+      .accountsPartial({ multisig })
+      .instruction();
+    const clearRoleIx = await program.methods
+      .setOwnerRole(multisigId, owners[0].publicKey, role(""))
+      .accountsPartial({ multisig })
+      .instruction();
+
+    for (const ix of [removeIx, clearRoleIx]) {
+      try {
+        await proposeAndExecute(multisigId, ix, [owners[0]]);
+        assert.fail("the change should have been rejected");
+      } catch (err) {
+        assert.include(err.toString(), "RoleQuorumUnreachable");
+      }
+    }
+
+    const account = await program.account.multisig.fetch(multisig);
+    assert.equal(account.owners.length, 2);
+
+    const health = await program.methods.healthCheck(multisigId).accountsPartial({ multisig }).view();
+    assert.isTrue(health.live);
+    assert.isTrue(health.roleQuorumReachable);
+  });
+
+  it("rejects a requirement no owner can meet", async () => {
+    const { owners, multisigId, multisig } = await setup();
+
+    const requireIx = await program.methods
+      .setRequiredRoles(multisigId, [{ role: role("SECURITY"), count: 2 }])
+      .accountsPartial({ multisig })
+      .instruction();
+
+    try {
+      await proposeAndExecute(multisigId, requireIx, [owners[0]]);
+      assert.fail("set_required_roles should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "RoleQuorumUnreachable");
+    }
+  });
+});
+
+describe("nonce dependencies", () => {
+  const proposeAfter = async (multisigId: BN, ix: anchor.web3.TransactionInstruction, proposer: Keypair, dependsOn: BN) => {
+    const multisig = multisigPda(multisigId);
+    const nonce = await nextNonce(multisig);
+
+    await program.methods
+      .createTransaction(multisigId, nonce, [
+        { programId: ix.programId, accounts: ix.keys, data: ix.data },
+      ], new BN(0), new BN(0), dependsOn, null, null, new BN(0), new BN(0), 0)
+      .accountsPartial({
+        proposer: proposer.publicKey,
+        multisig,
+        transaction: transactionPda(multisig, nonce),
+        nonceAccount: null,
+        recentBlockhashes: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([proposer])
+      .rpc();
+
+    return nonce;
+  };
+
+  it("keeps a dependency open until its dependent has executed", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const first = await propose(multisigId, ix, owner);
+    const second = await proposeAfter(multisigId, ix, owner, first);
+    const dependencyAccounts = [
+      ...selfCallAccounts(multisig),
+      { pubkey: transactionPda(multisig, first), isSigner: false, isWritable: false },
+    ];
+
+    await approve(multisigId, second, owner);
+    try {
+      await execute(multisigId, second, owner, dependencyAccounts);
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "DependencyNotExecuted");
+    }
+
+    await approveAndExecute(multisigId, first, [owner], selfCallAccounts(multisig));
+
+    // Closing the executed dependency would strand the dependent
+    const close = (nonce: BN) =>
+      program.methods
+        .closeTransaction(multisigId, nonce)
+        .accountsPartial({ multisig, transaction: transactionPda(multisig, nonce), proposer: owner.publicKey })
+        .rpc();
+    try {
+      await close(first);
+      assert.fail("close_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "TransactionPinned");
+    }
+
+    // Anyone may execute; a fresh executor keeps this from repeating the rejected transaction
+    await execute(multisigId, second, Keypair.generate(), dependencyAccounts);
+    assert.isTrue((await program.account.transaction.fetch(transactionPda(multisig, second))).didExecute);
+
+    // With the dependent done the pin is gone
+    await close(first);
+    assert.isEmpty((await program.account.multisig.fetch(multisig)).pinnedNonces);
+  });
+
+  it("rejects a dependency on a proposal that isn't open", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await nextNonce(multisig);
+
+    // Its own nonce, then one that hasn't been proposed yet
+    for (const dependsOn of [nonce, nonce.addn(5)]) {
+      try {
+        await proposeAfter(multisigId, ix, owner, dependsOn);
+        assert.fail("create_transaction should have been rejected");
+      } catch (err) {
+        assert.include(err.toString(), "InvalidDependency");
+      }
+    }
+  });
+});
+
+describe("chunked execution", () => {
+  // Proposes the same self call twice in one batch
+  const proposeBatch = async (multisigId: BN, ix: anchor.web3.TransactionInstruction, proposer: Keypair) => {
+    const multisig = multisigPda(multisigId);
+    const nonce = await nextNonce(multisig);
+    const stored = { programId: ix.programId, accounts: ix.keys, data: ix.data };
+
+    await program.methods
+      .createTransaction(multisigId, nonce, [stored, stored], new BN(0), new BN(0), null, null, null, new BN(0), new BN(0), 0)
+      .accountsPartial({
+        proposer: proposer.publicKey,
+        multisig,
+        transaction: transactionPda(multisig, nonce),
+        nonceAccount: null,
+        recentBlockhashes: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([proposer])
+      .rpc();
+
+    return nonce;
+  };
+
+  const executeChunk = (multisigId: BN, nonce: BN, executor: Keypair, startIndex: number) => {
+    const multisig = multisigPda(multisigId);
+
+    return program.methods
+      .executeTransaction(multisigId, nonce, startIndex, 1)
+      .accountsPartial({ executor: executor.publicKey, multisig, transaction: transactionPda(multisig, nonce) })
+      .remainingAccounts(selfCallAccounts(multisig))
+      .signers([executor])
+      .rpc();
+  };
+
+  it("resumes a batch from executed_count and refuses to cancel it midway", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await proposeBatch(multisigId, ix, owner);
+    const transaction = transactionPda(multisig, nonce);
+
+    await approve(multisigId, nonce, owner);
+    await executeChunk(multisigId, nonce, owner, 0);
+
+    let account = await program.account.transaction.fetch(transaction);
+    assert.equal(account.executedCount, 1);
+    assert.isFalse(account.didExecute);
+
+    // The first chunk can't run twice. Anyone may execute, and a fresh
+    // executor keeps the retry from being deduplicated
+    try {
+      await executeChunk(multisigId, nonce, Keypair.generate(), 0);
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidChunk");
+    }
+
+    try {
+      await program.methods
+        .cancelTransaction(multisigId, nonce)
+        .accountsPartial({ canceller: owner.publicKey, multisig, transaction, proposer: owner.publicKey })
+        .signers([owner])
+        .rpc();
+      assert.fail("cancel_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "BatchInProgress");
+    }
+
+    await executeChunk(multisigId, nonce, owner, 1);
+
+    account = await program.account.transaction.fetch(transaction);
+    assert.equal(account.executedCount, 2);
+    assert.isTrue(account.didExecute);
+  });
+
+  it("re-checks the threshold before every chunk", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await fund(owners[0]);
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2);
+    const ix = await program.methods.changeThreshold(multisigId, 2).accountsPartial({ multisig }).instruction();
+    const nonce = await proposeBatch(multisigId, ix, owners[0]);
+    const transaction = transactionPda(multisig, nonce);
+
+    await approve(multisigId, nonce, owners[0]);
+    await approve(multisigId, nonce, owners[1]);
+    await executeChunk(multisigId, nonce, owners[0], 0);
+
+    await program.methods
+      .revokeApproval(multisigId, nonce)
+      .accountsPartial({ owner: owners[1].publicKey, multisig, transaction })
+      .signers([owners[1]])
+      .rpc();
+
+    try {
+      await executeChunk(multisigId, nonce, owners[0], 1);
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "NotEnoughApprovals");
+    }
+
+    const account = await program.account.transaction.fetch(transaction);
+    assert.equal(account.executedCount, 1);
+    assert.isFalse(account.didExecute);
+  });
+});
+
+describe("closing a multisig", () => {
+  const closeAccounts = (multisig: PublicKey, creator: PublicKey) => [
+    ...selfCallAccounts(multisig),
+    { pubkey: vaultPda(multisig), isSigner: false, isWritable: false },
+    { pubkey: creator, isSigner: false, isWritable: true },
+  ];
+
+  const closeIx = (multisigId: BN, creator: PublicKey) => {
+    const multisig = multisigPda(multisigId);
+
+    return program.methods
+      .closeMultisig(multisigId)
+      .accountsPartial({ multisig, vault: vaultPda(multisig), creator })
+      .instruction();
+  };
+
+  it("closes an empty multisig and marks the closing proposal executed", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const nonce = await propose(multisigId, await closeIx(multisigId, owner.publicKey), owner);
+    await approveAndExecute(multisigId, nonce, [owner], closeAccounts(multisig, owner.publicKey));
+
+    assert.isNull(await provider.connection.getAccountInfo(multisig));
+    assert.isTrue((await program.account.transaction.fetch(transactionPda(multisig, nonce))).didExecute);
+  });
+
+  it("refuses to close while another proposal is open", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const changeIx = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    await propose(multisigId, changeIx, owner);
+
+    const nonce = await propose(multisigId, await closeIx(multisigId, owner.publicKey), owner);
+    try {
+      await approveAndExecute(multisigId, nonce, [owner], closeAccounts(multisig, owner.publicKey));
+      assert.fail("close_multisig should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "MultisigNotEmpty");
+    }
+
+    assert.isNotNull(await provider.connection.getAccountInfo(multisig));
+  });
+
+  it("rejects a proposal with instructions after close_multisig", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const close = await closeIx(multisigId, owner.publicKey);
+    const changeIx = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await nextNonce(multisig);
+
+    try {
+      await program.methods
+        .createTransaction(multisigId, nonce, [close, changeIx].map((ix) => (
+          { programId: ix.programId, accounts: ix.keys, data: ix.data }
+        )), new BN(0), new BN(0), null, null, null, new BN(0), new BN(0), 0)
+        .accountsPartial({
+          proposer: owner.publicKey,
+          multisig,
+          transaction: transactionPda(multisig, nonce),
+          nonceAccount: null,
+          recentBlockhashes: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      assert.fail("create_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "CloseMultisigNotLast");
+    }
+  });
+});
+
+describe("idempotent proposals", () => {
+  const proposeIdempotent = async (multisigId: BN, nonce: BN, ix: anchor.web3.TransactionInstruction, proposer: Keypair) => {
+    const multisig = multisigPda(multisigId);
+
+    await program.methods
+      .createTransactionIdempotent(multisigId, nonce, [
+        { programId: ix.programId, accounts: ix.keys, data: ix.data },
+      ], new BN(0), new BN(0), null, null, null, new BN(0), new BN(0), 0)
+      .accountsPartial({
+        proposer: proposer.publicKey,
+        multisig,
+        transaction: transactionPda(multisig, nonce),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([proposer])
+      .rpc();
+  };
+
+  it("accepts a retry of the same proposal", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await nextNonce(multisig);
+    const transaction = transactionPda(multisig, nonce);
+
+    await proposeIdempotent(multisigId, nonce, ix, owner);
+    const { txHash } = await program.account.transaction.fetch(transaction);
+    const balance = await provider.connection.getBalance(owner.publicKey);
+
+    // The retry charges no further rent
+    await proposeIdempotent(multisigId, nonce, ix, owner);
+
+    assert.deepEqual((await program.account.transaction.fetch(transaction)).txHash, txHash);
+    assert.isAtLeast(await provider.connection.getBalance(owner.publicKey), balance - 10_000);
+  });
+
+  it("rejects a different proposal under the same nonce", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 1);
+    const nonce = await nextNonce(multisig);
+
+    await proposeIdempotent(
+      multisigId,
+      nonce,
+      await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction(),
+      owners[0]
+    );
+
+    const other = await program.methods.changeThreshold(multisigId, 2).accountsPartial({ multisig }).instruction();
+    for (const proposer of owners) {
+      try {
+        await proposeIdempotent(multisigId, nonce, other, proposer);
+        assert.fail("create_transaction_idempotent should have been rejected");
+      } catch (err) {
+        assert.include(err.toString(), "NonceAlreadyUsed");
+      }
+    }
+  });
+});
+
+describe("weight thresholds", () => {
+  // Two heavy owners and a light one, 601 in total
+  const initializeWeighted = async (owners: Keypair[], weightThreshold: number) => {
+    const multisigId = new BN(Date.now());
+    const multisig = multisigPda(multisigId);
+
+    await program.methods
+      .initialize(multisigId, owners.map((o) => o.publicKey), 1, new BN(0), [300, 300, 1], new Array(32).fill(0), new BN(0), true, false, 1024, 1, null, new BN(weightThreshold))
+      .accountsPartial({
+        multisig,
+        creator: owners[0].publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owners[0]])
+      .rpc();
+
+    return { multisigId, multisig };
+  };
+
+  it("requires a weight sum above 255", async () => {
+    const owners = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeWeighted(owners, 600);
+    const ix = await program.methods.setWeightThreshold(multisigId, new BN(301)).accountsPartial({ multisig }).instruction();
+
+    // One heavy owner and the light one reach 301, not 600
+    const nonce = await propose(multisigId, ix, owners[0]);
+    await approve(multisigId, nonce, owners[0]);
+    await approve(multisigId, nonce, owners[2]);
+    try {
+      await execute(multisigId, nonce, owners[0], selfCallAccounts(multisig));
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "NotEnoughApprovals");
+    }
+
+    await approveAndExecute(multisigId, nonce, [owners[1]], selfCallAccounts(multisig));
+
+    const health = await program.methods.healthCheck(multisigId).accountsPartial({ multisig }).view();
+    assert.equal(health.requiredWeight.toNumber(), 301);
+    assert.equal(health.totalWeight.toNumber(), 601);
+  });
+
+  it("rejects a weight threshold above the total weight", async () => {
+    const owners = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    try {
+      await initializeWeighted(owners, 602);
+      assert.fail("initialize should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidThreshold");
+    }
+
+    const { multisigId, multisig } = await initializeWeighted(owners, 300);
+    const ix = await program.methods.setWeightThreshold(multisigId, new BN(602)).accountsPartial({ multisig }).instruction();
+    try {
+      await proposeAndExecute(multisigId, ix, [owners[0]]);
+      assert.fail("set_weight_threshold should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "InvalidThreshold");
+    }
+  });
+});
+
+describe("adding owners", () => {
+  // The vault pays for the extra owner slot and execute_transaction signs for it
+  const addOwnerAccounts = (multisig: PublicKey) => [
+    ...selfCallAccounts(multisig),
+    { pubkey: vaultPda(multisig), isSigner: false, isWritable: true },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+  ];
+
+  it("adds an owner through an approved proposal", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    await deposit(owner, multisig, 0.1 * LAMPORTS_PER_SOL);
+
+    const newOwner = Keypair.generate().publicKey;
+    const ix = await program.methods
+      .addOwner(multisigId, newOwner)
+      .accountsPartial({ multisig, payer: vaultPda(multisig), systemProgram: SystemProgram.programId })
+      .instruction();
+    const nonce = await propose(multisigId, ix, owner);
+    await approveAndExecute(multisigId, nonce, [owner], addOwnerAccounts(multisig));
+
+    const account = await program.account.multisig.fetch(multisig);
+    assert.equal(account.owners.length, 2);
+    assert.isTrue(account.owners[1].equals(newOwner));
+  });
+
+  it("rejects adding an existing owner", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    await deposit(owner, multisig, 0.1 * LAMPORTS_PER_SOL);
+
+    const ix = await program.methods
+      .addOwner(multisigId, owner.publicKey)
+      .accountsPartial({ multisig, payer: vaultPda(multisig), systemProgram: SystemProgram.programId })
+      .instruction();
+    const nonce = await propose(multisigId, ix, owner);
+    try {
+      await approveAndExecute(multisigId, nonce, [owner], addOwnerAccounts(multisig));
+      assert.fail("add_owner should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "AlreadyAnOwner");
+    }
+  });
+});
+
+describe("cancelling proposals", () => {
+  const cancel = (multisigId: BN, nonce: BN, canceller: Keypair, proposer: PublicKey) => {
+    const multisig = multisigPda(multisigId);
+
+    return program.methods
+      .cancelTransaction(multisigId, nonce)
+      .accountsPartial({ canceller: canceller.publicKey, multisig, transaction: transactionPda(multisig, nonce), proposer })
+      .signers([canceller])
+      .rpc();
+  };
+
+  it("closes a pending proposal and refunds the proposer", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2);
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await propose(multisigId, ix, owners[0]);
+    const transaction = transactionPda(multisig, nonce);
+
+    const rent = await provider.connection.getBalance(transaction);
+    const balanceBefore = await provider.connection.getBalance(owners[0].publicKey);
+
+    // Any owner may cancel, not just the proposer
+    await cancel(multisigId, nonce, owners[1], owners[0].publicKey);
+
+    assert.equal((await provider.connection.getBalance(owners[0].publicKey)) - balanceBefore, rent);
+    assert.isNull(await provider.connection.getAccountInfo(transaction));
+    assert.equal((await program.account.multisig.fetch(multisig)).openTxCount, 0);
+  });
+
+  it("rejects cancelling an executed proposal or by an outsider", async () => {
+    const owner = Keypair.generate();
+    const outsider = Keypair.generate();
+    await Promise.all([owner, outsider].map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+
+    const pending = await propose(multisigId, ix, owner);
+    try {
+      await cancel(multisigId, pending, outsider, owner.publicKey);
+      assert.fail("cancel_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "NotOwner");
+    }
+
+    await approveAndExecute(multisigId, pending, [owner], selfCallAccounts(multisig));
+    try {
+      await cancel(multisigId, pending, owner, owner.publicKey);
+      assert.fail("cancel_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "AlreadyExecuted");
+    }
+  });
+});
+
+describe("revoking approvals", () => {
+  const revoke = (multisigId: BN, nonce: BN, owner: Keypair) => {
+    const multisig = multisigPda(multisigId);
+
+    return program.methods
+      .revokeApproval(multisigId, nonce)
+      .accountsPartial({ owner: owner.publicKey, multisig, transaction: transactionPda(multisig, nonce) })
+      .signers([owner])
+      .rpc();
+  };
+
+  it("stops counting a revoked approval", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2);
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await propose(multisigId, ix, owners[0]);
+
+    await approve(multisigId, nonce, owners[0]);
+    await approve(multisigId, nonce, owners[1]);
+    await revoke(multisigId, nonce, owners[1]);
+
+    try {
+      await execute(multisigId, nonce, owners[0], selfCallAccounts(multisig));
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "NotEnoughApprovals");
+    }
+    assert.equal((await program.account.transaction.fetch(transactionPda(multisig, nonce))).approvalBitmap.toNumber(), 0b01);
+  });
+
+  it("rejects revoking a missing approval or an executed proposal", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 1);
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await propose(multisigId, ix, owners[0]);
+
+    try {
+      await revoke(multisigId, nonce, owners[1]);
+      assert.fail("revoke_approval should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "ApprovalNotFound");
+    }
+
+    await approveAndExecute(multisigId, nonce, [owners[0]], selfCallAccounts(multisig));
+    try {
+      await revoke(multisigId, nonce, owners[0]);
+      assert.fail("revoke_approval should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "AlreadyExecuted");
+    }
+  });
+});
+
+describe("pausing", () => {
+  const setPaused = (multisigId: BN, authority: Keypair, paused: boolean) =>
+    program.methods
+      .setPaused(multisigId, paused)
+      .accountsPartial({ authority: authority.publicKey, multisig: multisigPda(multisigId) })
+      .signers([authority])
+      .rpc();
+
+  it("blocks proposals and execution while approvals still work", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const nonce = await propose(multisigId, ix, owner);
+
+    await setPaused(multisigId, owner, true);
+    await approve(multisigId, nonce, owner);
+
+    try {
+      await propose(multisigId, ix, owner);
+      assert.fail("create_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "MultisigPaused");
+    }
+    try {
+      await execute(multisigId, nonce, owner, selfCallAccounts(multisig));
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "MultisigPaused");
+    }
+
+    await setPaused(multisigId, owner, false);
+    await execute(multisigId, nonce, Keypair.generate(), selfCallAccounts(multisig));
+    assert.isTrue((await program.account.transaction.fetch(transactionPda(multisig, nonce))).didExecute);
+  });
+
+  it("rejects a pause by anyone but the creator", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 1);
+
+    try {
+      await setPaused(multisigId, owners[1], true);
+      assert.fail("set_paused should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "NotCreator");
+    }
+    assert.isFalse((await program.account.multisig.fetch(multisig)).paused);
+  });
+});
+
+describe("daily limit", () => {
+  // Single-owner multisig with a 0.1 SOL daily limit and 0.5 SOL in the vault
+  const setup = async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+
+    const limitIx = await program.methods
+      .setDailyLimit(multisigId, new BN(0.1 * LAMPORTS_PER_SOL))
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, limitIx, [owner]);
+    await deposit(owner, multisig, 0.5 * LAMPORTS_PER_SOL);
+
+    return { owner, multisigId, multisig };
+  };
+
+  const fastTransfer = (multisigId: BN, owner: Keypair, recipient: PublicKey, lamports: number) => {
+    const multisig = multisigPda(multisigId);
+
+    return program.methods
+      .fastTransfer(multisigId, recipient, new BN(lamports))
+      .accountsPartial({
+        owner: owner.publicKey,
+        multisig,
+        vault: vaultPda(multisig),
+        recipient,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+  };
+
+  it("lets a single owner transfer within the daily limit", async () => {
+    const { owner, multisigId, multisig } = await setup();
+    const recipient = Keypair.generate().publicKey;
+
+    await fastTransfer(multisigId, owner, recipient, 0.06 * LAMPORTS_PER_SOL);
+
+    assert.equal(await provider.connection.getBalance(recipient), 0.06 * LAMPORTS_PER_SOL);
+    assert.equal((await program.account.multisig.fetch(multisig)).spentToday.toNumber(), 0.06 * LAMPORTS_PER_SOL);
+  });
+
+  it("rejects a transfer that takes the day's total over the limit", async () => {
+    const { owner, multisigId } = await setup();
+    const recipient = Keypair.generate().publicKey;
+
+    await fastTransfer(multisigId, owner, recipient, 0.06 * LAMPORTS_PER_SOL);
+    try {
+      await fastTransfer(multisigId, owner, recipient, 0.05 * LAMPORTS_PER_SOL);
+      assert.fail("fast_transfer should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "DailyLimitExceeded");
+    }
+
+    assert.equal(await provider.connection.getBalance(recipient), 0.06 * LAMPORTS_PER_SOL);
+  });
+});
+
+describe("batch approvals", () => {
+  const approveMany = (multisigId: BN, nonces: BN[], txHashes: number[][], owner: Keypair) => {
+    const multisig = multisigPda(multisigId);
+
+    return program.methods
+      .approveMany(multisigId, nonces, txHashes)
+      .accountsPartial({ owner: owner.publicKey, multisig })
+      .remainingAccounts(
+        nonces.map((nonce) => ({ pubkey: transactionPda(multisig, nonce), isSigner: false, isWritable: true }))
+      )
+      .signers([owner])
+      .rpc();
+  };
+
+  it("approves several proposals and skips those already approved", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2);
+    const ix = await program.methods.changeThreshold(multisigId, 2).accountsPartial({ multisig }).instruction();
+    const nonces = [await propose(multisigId, ix, owners[0]), await propose(multisigId, ix, owners[0])];
+    await approve(multisigId, nonces[0], owners[1]);
+
+    const hashes = await Promise.all(
+      nonces.map(async (nonce) => (await program.account.transaction.fetch(transactionPda(multisig, nonce))).txHash)
+    );
+    await approveMany(multisigId, nonces, hashes, owners[1]);
+
+    for (const nonce of nonces) {
+      const account = await program.account.transaction.fetch(transactionPda(multisig, nonce));
+      // Bit 1: the second owner, approved once each
+      assert.equal(account.approvalBitmap.toNumber(), 0b10);
+    }
+  });
+
+  it("rejects more proposals than one batch allows", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId } = await initializeMultisig([owner], 1);
+    const nonces = Array.from({ length: 11 }, (_, i) => new BN(i));
+
+    try {
+      await approveMany(multisigId, nonces, nonces.map(() => new Array(32).fill(0)), owner);
+      assert.fail("approve_many should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "BatchTooLarge");
+    }
+  });
+});
+
+describe("rejections", () => {
+  const reject = (multisigId: BN, nonce: BN, owner: Keypair) => {
+    const multisig = multisigPda(multisigId);
+
+    return program.methods
+      .rejectTransaction(multisigId, nonce)
+      .accountsPartial({ owner: owner.publicKey, multisig, transaction: transactionPda(multisig, nonce) })
+      .signers([owner])
+      .rpc();
+  };
+
+  it("keeps a proposal alive while the threshold is still reachable", async () => {
+    const owners = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2);
+    const ix = await program.methods.changeThreshold(multisigId, 2).accountsPartial({ multisig }).instruction();
+    const nonce = await propose(multisigId, ix, owners[0]);
+
+    // One no-vote out of three still leaves 2-of-3 reachable
+    await reject(multisigId, nonce, owners[2]);
+    assert.isFalse((await program.account.transaction.fetch(transactionPda(multisig, nonce))).rejected);
+
+    await approveAndExecute(multisigId, nonce, [owners[0], owners[1]], selfCallAccounts(multisig));
+    assert.isTrue((await program.account.transaction.fetch(transactionPda(multisig, nonce))).didExecute);
+  });
+
+  it("kills a proposal once enough owners reject it", async () => {
+    const owners = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2);
+    const ix = await program.methods.changeThreshold(multisigId, 2).accountsPartial({ multisig }).instruction();
+    const nonce = await propose(multisigId, ix, owners[0]);
+    await approve(multisigId, nonce, owners[0]);
+
+    await reject(multisigId, nonce, owners[1]);
+    try {
+      await reject(multisigId, nonce, owners[1]);
+      assert.fail("reject_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "AlreadyRejected");
+    }
+    await reject(multisigId, nonce, owners[2]);
+    assert.isTrue((await program.account.transaction.fetch(transactionPda(multisig, nonce))).rejected);
+
+    try {
+      await execute(multisigId, nonce, owners[0], selfCallAccounts(multisig));
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "TransactionRejected");
+    }
+  });
+});
+
+describe("emergency withdraw", () => {
+  const emergencyWithdraw = (multisigId: BN, recovery: PublicKey, signers: Keypair[]) => {
+    const multisig = multisigPda(multisigId);
+
+    return program.methods
+      .emergencyWithdraw(multisigId, recovery)
+      .accountsPartial({ multisig, vault: vaultPda(multisig), recovery, systemProgram: SystemProgram.programId })
+      .remainingAccounts(signers.map((signer) => ({ pubkey: signer.publicKey, isSigner: true, isWritable: false })))
+      .signers(signers)
+      .rpc();
+  };
+
+  it("sweeps the vault when every owner signs", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 1);
+    await deposit(owners[0], multisig, 0.2 * LAMPORTS_PER_SOL);
+    const recovery = Keypair.generate().publicKey;
+
+    await emergencyWithdraw(multisigId, recovery, owners);
+
+    assert.equal(await provider.connection.getBalance(recovery), 0.2 * LAMPORTS_PER_SOL);
+    assert.equal(await provider.connection.getBalance(vaultPda(multisig)), 0);
+  });
+
+  it("rejects a sweep missing an owner's signature", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 1);
+    await deposit(owners[0], multisig, 0.2 * LAMPORTS_PER_SOL);
+
+    try {
+      await emergencyWithdraw(multisigId, Keypair.generate().publicKey, [owners[0]]);
+      assert.fail("emergency_withdraw should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "MissingOwnerSignature");
+    }
+    assert.equal(await provider.connection.getBalance(vaultPda(multisig)), 0.2 * LAMPORTS_PER_SOL);
+  });
+});
+
+describe("approval delegation", () => {
+  const setDelegate = (multisigId: BN, owner: Keypair, delegate: PublicKey | null) =>
+    program.methods
+      .setDelegate(multisigId, delegate)
+      .accountsPartial({ owner: owner.publicKey, multisig: multisigPda(multisigId) })
+      .signers([owner])
+      .rpc();
+
+  it("records a delegate's approval as its owner's", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    const hotKey = Keypair.generate();
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2);
+    await setDelegate(multisigId, owners[0], hotKey.publicKey);
+
+    const ix = await program.methods.changeThreshold(multisigId, 2).accountsPartial({ multisig }).instruction();
+    const nonce = await propose(multisigId, ix, owners[0]);
+    await approve(multisigId, nonce, hotKey);
+
+    // Bit 0: the cold owner
+    assert.equal((await program.account.transaction.fetch(transactionPda(multisig, nonce))).approvalBitmap.toNumber(), 0b01);
+
+    // The owner's vote is already in, so the owner can't add a second one
+    try {
+      await approve(multisigId, nonce, owners[0]);
+      assert.fail("approve_transaction should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "AlreadyApproved");
+    }
+
+    await approveAndExecute(multisigId, nonce, [owners[1]], selfCallAccounts(multisig));
+    assert.isTrue((await program.account.transaction.fetch(transactionPda(multisig, nonce))).didExecute);
+  });
+
+  it("rejects another owner or a taken hot key as delegate", async () => {
+    const owners = [Keypair.generate(), Keypair.generate()];
+    const hotKey = Keypair.generate();
+    await Promise.all(owners.map(fund));
+
+    const { multisigId } = await initializeMultisig(owners, 2);
+
+    try {
+      await setDelegate(multisigId, owners[0], owners[1].publicKey);
+      assert.fail("set_delegate should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "DelegateInUse");
+    }
+
+    await setDelegate(multisigId, owners[0], hotKey.publicKey);
+    try {
+      await setDelegate(multisigId, owners[1], hotKey.publicKey);
+      assert.fail("set_delegate should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "DelegateInUse");
+    }
+  });
+});
+
+describe("rotating the owner set", () => {
+  const setOwners = async (multisigId: BN, newOwners: PublicKey[], newThreshold: number, owner: Keypair) => {
+    const multisig = multisigPda(multisigId);
+    const vault = vaultPda(multisig);
+    const ix = await program.methods
+      .setOwners(multisigId, newOwners, newThreshold)
+      .accountsPartial({ multisig, payer: vault, systemProgram: SystemProgram.programId })
+      .instruction();
+    const nonce = await propose(multisigId, ix, owner);
+
+    // The vault pays for any owner slots the account has to grow by
+    await approveAndExecute(multisigId, nonce, [owner], [
+      ...selfCallAccounts(multisig),
+      { pubkey: vault, isSigner: false, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ]);
+  };
+
+  it("replaces the owners and threshold in one step", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    await deposit(owner, multisig, 0.1 * LAMPORTS_PER_SOL);
+
+    const newOwners = [Keypair.generate(), Keypair.generate(), Keypair.generate()].map((k) => k.publicKey);
+    await setOwners(multisigId, newOwners, 2, owner);
+
+    const account = await program.account.multisig.fetch(multisig);
+    assert.deepEqual(account.owners.map((o) => o.toBase58()), newOwners.map((o) => o.toBase58()));
+    assert.equal(account.threshold, 2);
+  });
+
+  it("rejects duplicate owners or an unreachable threshold", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    await deposit(owner, multisig, 0.1 * LAMPORTS_PER_SOL);
+    const other = Keypair.generate().publicKey;
+
+    for (const [newOwners, newThreshold, error] of [
+      [[owner.publicKey, other, other], 1, "DuplicateOwners"],
+      [[owner.publicKey, other], 3, "InvalidThreshold"],
+    ] as [PublicKey[], number, string][]) {
+      try {
+        await setOwners(multisigId, newOwners, newThreshold, owner);
+        assert.fail("set_owners should have been rejected");
+      } catch (err) {
+        assert.include(err.toString(), error);
+      }
+    }
+
+    assert.equal((await program.account.multisig.fetch(multisig)).owners.length, 1);
+  });
+});
+
+describe("batch proposals", () => {
+  const createBatch = (multisigId: BN, ix: anchor.web3.TransactionInstruction, nonces: BN[], pdaNonces: BN[], proposer: Keypair) => {
+    const multisig = multisigPda(multisigId);
+
+    return program.methods
+      .createTransactionsBatch(
+        multisigId,
+        nonces.map((nonce) => ({ nonce, programId: ix.programId, accounts: ix.keys, data: ix.data }))
+      )
+      .accountsPartial({ proposer: proposer.publicKey, multisig, systemProgram: SystemProgram.programId })
+      .remainingAccounts(
+        pdaNonces.map((nonce) => ({ pubkey: transactionPda(multisig, nonce), isSigner: false, isWritable: true }))
+      )
+      .signers([proposer])
+      .rpc();
+  };
+
+  it("creates one proposal per entry", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const seq = await nextNonce(multisig);
+    const nonces = [seq, seq.addn(1)];
+
+    await createBatch(multisigId, ix, nonces, nonces, owner);
+
+    for (const nonce of nonces) {
+      const account = await program.account.transaction.fetch(transactionPda(multisig, nonce));
+      assert.isTrue(account.proposer.equals(owner.publicKey));
+      assert.equal(account.nonce.toString(), nonce.toString());
+    }
+    const account = await program.account.multisig.fetch(multisig);
+    assert.equal(account.openTxCount, 2);
+    assert.equal(account.seq.toString(), seq.addn(2).toString());
+  });
+
+  it("rejects transaction accounts passed out of order", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    const ix = await program.methods.changeThreshold(multisigId, 1).accountsPartial({ multisig }).instruction();
+    const seq = await nextNonce(multisig);
+
+    try {
+      await createBatch(multisigId, ix, [seq, seq.addn(1)], [seq.addn(1), seq], owner);
+      assert.fail("create_transactions_batch should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "AccountMismatch");
+    }
+    assert.equal((await program.account.multisig.fetch(multisig)).openTxCount, 0);
+  });
+});
+
+describe("veto owners", () => {
+  it("lets a veto owner block a fully approved proposal", async () => {
+    const owners = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    await Promise.all(owners.map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig(owners, 2);
+    const vetoIx = await program.methods
+      .setVetoOwners(multisigId, [owners[2].publicKey])
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, vetoIx, [owners[0], owners[1]]);
+
+    const ix = await program.methods.changeThreshold(multisigId, 2).accountsPartial({ multisig }).instruction();
+    const nonce = await propose(multisigId, ix, owners[0]);
+    await approve(multisigId, nonce, owners[0]);
+    await approve(multisigId, nonce, owners[1]);
+
+    await program.methods
+      .rejectTransaction(multisigId, nonce)
+      .accountsPartial({ owner: owners[2].publicKey, multisig, transaction: transactionPda(multisig, nonce) })
+      .signers([owners[2]])
+      .rpc();
+
+    try {
+      await execute(multisigId, nonce, owners[0], selfCallAccounts(multisig));
+      assert.fail("execution should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "Vetoed");
+    }
+  });
+
+  it("rejects a veto owner who isn't an owner", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    const vetoIx = await program.methods
+      .setVetoOwners(multisigId, [Keypair.generate().publicKey])
+      .accountsPartial({ multisig })
+      .instruction();
+
+    try {
+      await proposeAndExecute(multisigId, vetoIx, [owner]);
+      assert.fail("set_veto_owners should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "NotOwner");
+    }
+    assert.isEmpty((await program.account.multisig.fetch(multisig)).vetoOwners);
+  });
+});
+
+describe("delayed spending limits", () => {
+  const setDailyLimit = async (multisigId: BN, owner: Keypair, lamports: number) => {
+    const multisig = multisigPda(multisigId);
+    const limitIx = await program.methods
+      .setDailyLimit(multisigId, new BN(lamports))
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, limitIx, [owner]);
+  };
+
+  it("applies a limit change at once without a governance delay", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    await setDailyLimit(multisigId, owner, 0.1 * LAMPORTS_PER_SOL);
+
+    const account = await program.account.multisig.fetch(multisig);
+    assert.equal(account.dailyLimit.toNumber(), 0.1 * LAMPORTS_PER_SOL);
+    assert.isNull(account.pendingGovernanceChange);
+  });
+
+  it("holds a raised limit back until the delay has passed", async () => {
+    const owner = Keypair.generate();
+    await fund(owner);
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    const setDelayIx = await program.methods
+      .setGovernanceDelay(multisigId, new BN(3600))
+      .accountsPartial({ multisig })
+      .instruction();
+    await proposeAndExecute(multisigId, setDelayIx, [owner]);
+    await deposit(owner, multisig, 0.5 * LAMPORTS_PER_SOL);
+
+    await setDailyLimit(multisigId, owner, 0.5 * LAMPORTS_PER_SOL);
+    assert.equal((await program.account.multisig.fetch(multisig)).dailyLimit.toNumber(), 0);
+
+    // The queued limit can't be spent against yet
+    const recipient = Keypair.generate().publicKey;
+    try {
+      await program.methods
+        .fastTransfer(multisigId, recipient, new BN(0.4 * LAMPORTS_PER_SOL))
+        .accountsPartial({
+          owner: owner.publicKey,
+          multisig,
+          vault: vaultPda(multisig),
+          recipient,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      assert.fail("fast_transfer should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "DailyLimitExceeded");
+    }
+
+    try {
+      await program.methods
+        .applyGovernanceChange(multisigId)
+        .accountsPartial({ payer: owner.publicKey, multisig })
+        .signers([owner])
+        .rpc();
+      assert.fail("apply_governance_change should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "GovernanceChangeNotReady");
+    }
+  });
+});
+
+describe("owner nomination", () => {
+  const nominate = async (multisigId: BN, candidate: PublicKey, owner: Keypair) => {
+    const multisig = multisigPda(multisigId);
+    const nominateIx = await program.methods.nominateOwner(multisigId, candidate).accountsPartial({ multisig }).instruction();
+    await proposeAndExecute(multisigId, nominateIx, [owner]);
+  };
+
+  const accept = (multisigId: BN, candidate: Keypair) =>
+    program.methods
+      .acceptOwnership(multisigId)
+      .accountsPartial({ candidate: candidate.publicKey, multisig: multisigPda(multisigId), systemProgram: SystemProgram.programId })
+      .signers([candidate])
+      .rpc();
+
+  it("adds the nominee once they accept", async () => {
+    const owner = Keypair.generate();
+    const candidate = Keypair.generate();
+    await Promise.all([owner, candidate].map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    await nominate(multisigId, candidate.publicKey, owner);
+    assert.equal((await program.account.multisig.fetch(multisig)).owners.length, 1);
+
+    await accept(multisigId, candidate);
+
+    const account = await program.account.multisig.fetch(multisig);
+    assert.isTrue(account.owners[1].equals(candidate.publicKey));
+    assert.isNull(account.pendingNomination);
+  });
+
+  it("rejects acceptance by anyone but the nominee", async () => {
+    const owner = Keypair.generate();
+    const candidate = Keypair.generate();
+    const impostor = Keypair.generate();
+    await Promise.all([owner, candidate, impostor].map(fund));
+
+    const { multisigId, multisig } = await initializeMultisig([owner], 1);
+    await nominate(multisigId, candidate.publicKey, owner);
+
+    try {
+      await accept(multisigId, impostor);
+      assert.fail("accept_ownership should have been rejected");
+    } catch (err) {
+      assert.include(err.toString(), "NoPendingNomination");
+    }
+
+    const account = await program.account.multisig.fetch(multisig);
+    assert.equal(account.owners.length, 1);
+    assert.isNotNull(account.pendingNomination);
+  });
+});